@@ -0,0 +1,62 @@
+//! Compares `Async::<TcpStream>::recv_exact` with a naive `read_exact`, which reads a single byte per call.
+//!
+//! Run with `cargo bench --bench recv_exact`.
+
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::time::Instant;
+
+use async_io_mini::Async;
+use futures_lite::{future, AsyncWriteExt};
+
+const RECORD: usize = 1024;
+const RECORDS: usize = 1000;
+
+async fn naive_read_exact(stream: &Async<TcpStream>, buf: &mut [u8]) -> io::Result<()> {
+    for byte in buf.chunks_mut(1) {
+        stream
+            .read_with(|mut io| io::Read::read_exact(&mut io, byte))
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let mut writer = Async::<TcpStream>::connect(addr).await?;
+        let reader = listener.accept().await?.0;
+
+        let record = [0x55; RECORD];
+        let mut buf = [0; RECORD];
+
+        let report = |name: &str, start: Instant| {
+            println!("{name}: {:?} per record", start.elapsed() / RECORDS as u32);
+        };
+
+        let start = Instant::now();
+
+        for _ in 0..RECORDS {
+            writer.write_all(&record).await?;
+            reader.recv_exact(&mut buf).await?;
+        }
+
+        // Each record is written at once, so it is typically received with a single call
+        report("recv_exact", start);
+
+        let start = Instant::now();
+
+        for _ in 0..RECORDS {
+            writer.write_all(&record).await?;
+            naive_read_exact(&reader, &mut buf).await?;
+        }
+
+        // One call per byte, i.e. `RECORD` calls per record
+        report("naive read_exact", start);
+
+        Ok(())
+    })
+}
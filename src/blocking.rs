@@ -0,0 +1,175 @@
+use core::future::{poll_fn, Future};
+use core::task::{Poll, Waker};
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+
+#[cfg(not(feature = "tracing"))]
+use log::{debug, error};
+#[cfg(feature = "tracing")]
+use tracing::{debug, error};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Pool {
+    jobs: VecDeque<Job>,
+    max_threads: usize,
+    threads: usize,
+    idle: usize,
+}
+
+static POOL: Mutex<Pool> = Mutex::new(Pool {
+    jobs: VecDeque::new(),
+    max_threads: 1,
+    threads: 0,
+    idle: 0,
+});
+
+static POOL_CONDVAR: Condvar = Condvar::new();
+
+/// Sets the maximum number of worker threads used by [`spawn_blocking`].
+///
+/// The worker threads are spawned lazily, when there are queued jobs and no idle worker threads, and they are
+/// never stopped. Lowering the maximum does not stop the already spawned worker threads.
+///
+/// Defaults to 1, given the stack cost of each thread on embedded targets.
+///
+/// Returns an error of kind [`io::ErrorKind::InvalidInput`] if `max_threads` is 0.
+pub fn set_blocking_threads(max_threads: usize) -> io::Result<()> {
+    if max_threads == 0 {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "at least one blocking thread is necessary",
+        ))?;
+    }
+
+    POOL.lock().unwrap().max_threads = max_threads;
+
+    Ok(())
+}
+
+/// Runs the blocking closure on a worker thread, and waits for its result without blocking the calling task.
+///
+/// This is useful for operations which are inherently blocking (e.g. file system access, `getaddrinfo`
+/// or expensive cryptography) and which would otherwise stall the executor and delay all other I/O.
+///
+/// The closure is submitted right away, rather than when the returned future is first polled.
+///
+/// The closures run on a bounded pool of worker threads (see [`set_blocking_threads`]); when all of them are busy,
+/// the closures are queued and run in order. If the closure panics, the panic is resumed in the awaiting task.
+///
+/// If not even a single worker thread can be spawned (e.g. for lack of memory), the closure is run inline, on the
+/// calling thread, rather than never.
+///
+/// Only available with the `blocking` feature.
+///
+/// # Examples
+///
+/// ```
+/// use async_io_mini::spawn_blocking;
+///
+/// # futures_lite::future::block_on(async {
+/// let contents = spawn_blocking(|| std::fs::read_to_string("Cargo.toml")).await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+pub fn spawn_blocking<T, F>(f: F) -> impl Future<Output = T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let slot = Arc::new(Mutex::new(Slot::<T> {
+        result: None,
+        waker: None,
+    }));
+
+    {
+        let slot = slot.clone();
+
+        submit(Box::new(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+
+            let waker = {
+                let mut slot = slot.lock().unwrap();
+
+                slot.result = Some(result);
+                slot.waker.take()
+            };
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }));
+    }
+
+    poll_fn(move |cx| {
+        let mut slot = slot.lock().unwrap();
+
+        match slot.result.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+
+                Poll::Pending
+            }
+        }
+    })
+}
+
+struct Slot<T> {
+    result: Option<std::thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+fn submit(job: Job) {
+    let mut pool = POOL.lock().unwrap();
+
+    pool.jobs.push_back(job);
+
+    if pool.idle == 0 && pool.threads < pool.max_threads {
+        let spawned = std::thread::Builder::new()
+            .name("async-io-mini-blocking".into())
+            .spawn(work);
+
+        match spawned {
+            Ok(_) => {
+                pool.threads += 1;
+
+                debug!("Spawned blocking thread #{}", pool.threads);
+            }
+            // The job is still served by the already spawned threads
+            Err(err) if pool.threads > 0 => error!("Failed to spawn a blocking thread: {err}"),
+            Err(err) => {
+                error!("Failed to spawn a blocking thread, running the job inline: {err}");
+
+                // Nobody would ever run the job otherwise, so the awaiting task would hang forever
+                let job = pool.jobs.pop_back().unwrap();
+
+                drop(pool);
+
+                job();
+            }
+        }
+    } else {
+        POOL_CONDVAR.notify_one();
+    }
+}
+
+fn work() {
+    let mut pool = POOL.lock().unwrap();
+
+    loop {
+        if let Some(job) = pool.jobs.pop_front() {
+            drop(pool);
+
+            job();
+
+            pool = POOL.lock().unwrap();
+        } else {
+            pool.idle += 1;
+            pool = POOL_CONDVAR.wait(pool).unwrap();
+            pool.idle -= 1;
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll, Waker};
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// A cheaply cloneable cancellation signal.
+///
+/// All clones share the same state, so the token can be passed down a task tree and cancelled from
+/// anywhere. Cancelling the token wakes all futures currently waiting on it, e.g. the readiness waits of
+/// [`Async::read_cancellable`](crate::Async::read_cancellable) and
+/// [`Async::write_cancellable`](crate::Async::write_cancellable), which then fail with
+/// [`io::ErrorKind::Interrupted`].
+///
+/// Cancellation is permanent: once cancelled, a token stays cancelled.
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_io_mini::{Async, CancelToken};
+/// use std::net::TcpStream;
+///
+/// # futures_lite::future::block_on(async {
+/// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+///
+/// let token = CancelToken::new();
+/// let canceller = token.clone();
+///
+/// // Somewhere else, e.g. on shutdown
+/// canceller.cancel();
+///
+/// let mut buf = [0u8; 1024];
+///
+/// match stream.read_cancellable(&mut buf, &token).await {
+///     Ok(len) => println!("Read {len} bytes"),
+///     Err(err) if err.kind() == std::io::ErrorKind::Interrupted => println!("Cancelled"),
+///     Err(err) => Err(err)?,
+/// }
+/// # std::io::Result::Ok(()) });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<Mutex<State>>);
+
+#[derive(Debug, Default)]
+struct State {
+    cancelled: bool,
+    wakers: Vec<Option<Waker>>,
+}
+
+impl CancelToken {
+    /// Creates a new, not yet cancelled token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token, waking all futures waiting on it.
+    pub fn cancel(&self) {
+        let mut state = self.0.lock().unwrap();
+
+        state.cancelled = true;
+
+        for waker in state.wakers.drain(..).flatten() {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.lock().unwrap().cancelled
+    }
+
+    /// Waits until the token is cancelled.
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled {
+            token: self,
+            slot: None,
+        }
+    }
+
+    /// Runs `fut` until it completes or until the token is cancelled, in which case an error of kind
+    /// [`io::ErrorKind::Interrupted`] is returned.
+    pub(crate) async fn run<R>(&self, fut: impl Future<Output = io::Result<R>>) -> io::Result<R> {
+        let mut fut = pin!(fut);
+        let mut cancelled = pin!(self.cancelled());
+
+        core::future::poll_fn(move |cx| {
+            // Check for cancellation first, so that an already cancelled token does not let any I/O through
+            if cancelled.as_mut().poll(cx).is_ready() {
+                Poll::Ready(Err(io::ErrorKind::Interrupted.into()))
+            } else {
+                fut.as_mut().poll(cx)
+            }
+        })
+        .await
+    }
+}
+
+/// A future which resolves once its [`CancelToken`] is cancelled.
+///
+/// Created by [`CancelToken::cancelled`].
+#[derive(Debug)]
+pub struct Cancelled<'a> {
+    token: &'a CancelToken,
+    slot: Option<usize>,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.token.0.lock().unwrap();
+
+        if state.cancelled {
+            return Poll::Ready(());
+        }
+
+        match self.slot {
+            Some(slot) => {
+                let waker = &mut state.wakers[slot];
+
+                if !waker
+                    .as_ref()
+                    .is_some_and(|waker| waker.will_wake(cx.waker()))
+                {
+                    *waker = Some(cx.waker().clone());
+                }
+            }
+            None => {
+                // Reuse the slots of dropped futures, so that a long-lived token does not grow unboundedly
+                let slot = if let Some(slot) = state.wakers.iter().position(Option::is_none) {
+                    state.wakers[slot] = Some(cx.waker().clone());
+                    slot
+                } else {
+                    state.wakers.push(Some(cx.waker().clone()));
+                    state.wakers.len() - 1
+                };
+
+                drop(state);
+
+                self.slot = Some(slot);
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot {
+            let mut state = self.token.0.lock().unwrap();
+
+            // The slots are drained on cancellation
+            if let Some(waker) = state.wakers.get_mut(slot) {
+                *waker = None;
+            }
+        }
+    }
+}
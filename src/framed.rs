@@ -0,0 +1,197 @@
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use std::io;
+
+use futures_lite::{AsyncRead, Stream};
+
+/// The format of the length prefix of the frames read by [`FramedRead`].
+///
+/// The prefix holds the length of the frame payload, excluding the prefix itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+    /// A big-endian (network byte order) `u16`.
+    U16Be,
+    /// A little-endian `u16`.
+    U16Le,
+    /// A big-endian (network byte order) `u32`.
+    U32Be,
+    /// A little-endian `u32`.
+    U32Le,
+}
+
+impl LengthPrefix {
+    /// Returns the size of the prefix, in bytes.
+    pub const fn size(&self) -> usize {
+        match self {
+            Self::U16Be | Self::U16Le => 2,
+            Self::U32Be | Self::U32Le => 4,
+        }
+    }
+
+    fn decode(&self, header: &[u8; 4]) -> usize {
+        match self {
+            Self::U16Be => u16::from_be_bytes([header[0], header[1]]) as usize,
+            Self::U16Le => u16::from_le_bytes([header[0], header[1]]) as usize,
+            Self::U32Be => u32::from_be_bytes(*header) as usize,
+            Self::U32Le => u32::from_le_bytes(*header) as usize,
+        }
+    }
+}
+
+/// A [`Stream`] of length-prefixed frames read from an [`AsyncRead`] (e.g. an [`Async<TcpStream>`](crate::Async)).
+///
+/// Each item is the payload of one frame, without the length prefix. Frames may arrive in arbitrarily small
+/// pieces, which are accumulated across readiness edges.
+///
+/// The stream ends when the reader reaches EOF on a frame boundary. It fails with an error of kind
+/// [`io::ErrorKind::UnexpectedEof`] if EOF is reached in the middle of a frame, and of kind
+/// [`io::ErrorKind::InvalidData`] if a frame declares a length above the maximum frame size (see
+/// [`FramedRead::max_frame_size`]), which guards against allocating absurd amounts of memory. After any error,
+/// the stream ends, as the frame boundaries are lost.
+///
+/// Only available with the `framed` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_io_mini::{Async, FramedRead, LengthPrefix};
+/// use futures_lite::StreamExt;
+/// use std::net::TcpStream;
+///
+/// # futures_lite::future::block_on(async {
+/// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+///
+/// let mut frames = FramedRead::new(stream, LengthPrefix::U16Be).max_frame_size(4096);
+///
+/// while let Some(frame) = frames.next().await {
+///     println!("Received a frame of {} bytes", frame?.len());
+/// }
+/// # std::io::Result::Ok(()) });
+/// ```
+#[derive(Debug)]
+pub struct FramedRead<R> {
+    reader: R,
+    prefix: LengthPrefix,
+    max_frame_size: usize,
+    header: [u8; 4],
+    header_len: usize,
+    frame: Option<Vec<u8>>,
+    frame_len: usize,
+    done: bool,
+}
+
+impl<R> FramedRead<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Creates a new stream of frames with the provided length prefix format.
+    ///
+    /// The maximum frame size defaults to 64KiB.
+    pub const fn new(reader: R, prefix: LengthPrefix) -> Self {
+        Self {
+            reader,
+            prefix,
+            max_frame_size: 64 * 1024,
+            header: [0; 4],
+            header_len: 0,
+            frame: None,
+            frame_len: 0,
+            done: false,
+        }
+    }
+
+    /// Sets the maximum size of a frame payload, in bytes.
+    pub const fn max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Gets a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Unwraps the inner reader, dropping the partially read frame, if any.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn poll_frame(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<Vec<u8>>>> {
+        let prefix_size = self.prefix.size();
+
+        while self.frame.is_none() {
+            if self.header_len == prefix_size {
+                let len = self.prefix.decode(&self.header);
+
+                if len > self.max_frame_size {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "frame length exceeds the maximum frame size",
+                    ))?;
+                }
+
+                self.frame = Some(vec![0; len]);
+                self.frame_len = 0;
+            } else {
+                let len = match Pin::new(&mut self.reader)
+                    .poll_read(cx, &mut self.header[self.header_len..prefix_size])
+                {
+                    Poll::Ready(result) => result?,
+                    Poll::Pending => return Poll::Pending,
+                };
+
+                if len == 0 {
+                    if self.header_len == 0 {
+                        // EOF on a frame boundary
+                        return Poll::Ready(None);
+                    }
+
+                    Err(io::ErrorKind::UnexpectedEof)?;
+                }
+
+                self.header_len += len;
+            }
+        }
+
+        let frame = self.frame.as_mut().unwrap();
+
+        while self.frame_len < frame.len() {
+            let len = match Pin::new(&mut self.reader).poll_read(cx, &mut frame[self.frame_len..]) {
+                Poll::Ready(result) => result?,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            if len == 0 {
+                Err(io::ErrorKind::UnexpectedEof)?;
+            }
+
+            self.frame_len += len;
+        }
+
+        self.header_len = 0;
+
+        Poll::Ready(self.frame.take().map(Ok))
+    }
+}
+
+impl<R> Stream for FramedRead<R>
+where
+    R: AsyncRead + Unpin,
+{
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let result = self.poll_frame(cx);
+
+        if matches!(result, Poll::Ready(None) | Poll::Ready(Some(Err(_)))) {
+            self.done = true;
+        }
+
+        result
+    }
+}
@@ -3,10 +3,12 @@ use core::pin::pin;
 use core::task::{Context, Poll};
 
 use std::io::{self, Read, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, UdpSocket};
 use std::os::fd::FromRawFd;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
 
+use enumset::EnumSet;
+
 use super::reactor::{Event, REACTOR};
 use super::sys;
 use super::{ready, syscall, syscall_los, syscall_los_eagain};
@@ -66,8 +68,14 @@ use super::{ready, syscall, syscall_los, syscall_los_eagain};
 /// [`poll_readable()`][`Async::poll_readable()`] and
 /// [`poll_writable()`][`Async::poll_writable()`].
 ///
-/// However, any number of tasks can be concurrently calling other methods like
-/// [`readable()`][`Async::readable()`] or [`read_with()`][`Async::read_with()`].
+/// Unlike `async-io`, the reactor of this crate keeps a single waker per file descriptor and
+/// direction, so the caveat also applies to other methods like [`readable()`][`Async::readable()`]
+/// or [`read_with()`][`Async::read_with()`]. These remain correct when called concurrently - when
+/// a task replaces the waker of another task, the other task is woken up, so no wakeup is ever lost -
+/// but the tasks keep waking each other in turn until the file descriptor becomes ready. Once it does,
+/// only one of the tasks observes the readiness; if it does not consume all of the data, the next
+/// attempt of the other task to read would succeed, otherwise it gets [`io::ErrorKind::WouldBlock`]
+/// and simply waits again.
 ///
 /// ### Closing
 ///
@@ -167,8 +175,19 @@ impl<T: AsFd> Async<T> {
     /// and cause a deadlock in an asynchronous context.
     pub fn new_nonblocking(io: T) -> io::Result<Self> {
         REACTOR.start()?;
+
+        let fd = io.as_fd().as_raw_fd();
+
         // SAFETY: It is impossible to drop the I/O source while it is registered.
-        REACTOR.register(io.as_fd().as_raw_fd())?;
+        if let Err(err) = REACTOR.register(fd) {
+            if err.kind() == io::ErrorKind::TimedOut {
+                // The registration is in place even though the reactor did not acknowledge it in time,
+                // so undo it before `io` is dropped and closed
+                let _ = REACTOR.deregister(fd);
+            }
+
+            Err(err)?;
+        }
 
         Ok(Self { io: Some(io) })
     }
@@ -282,7 +301,10 @@ impl<T: AsFd> Async<T> {
     /// # std::io::Result::Ok(()) });
     /// ```
     pub async fn readable(&self) -> io::Result<()> {
-        poll_fn(|cx| self.poll_readable(cx)).await
+        REACTOR
+            .ready(self.as_fd().as_raw_fd(), Event::Read.into(), None)
+            .await
+            .map(|_| ())
     }
 
     /// Waits until the I/O handle is writable.
@@ -304,7 +326,51 @@ impl<T: AsFd> Async<T> {
     /// # std::io::Result::Ok(()) });
     /// ```
     pub async fn writable(&self) -> io::Result<()> {
-        poll_fn(|cx| self.poll_writable(cx)).await
+        REACTOR
+            .ready(self.as_fd().as_raw_fd(), Event::Write.into(), None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Waits until the I/O handle is readable, failing with the pending socket error, if any.
+    ///
+    /// Socket errors (e.g. a connection reset by the peer) make the socket ready, so [`readable()`][`Async::readable()`]
+    /// completes, and the subsequent read fails. This variant takes the pending error (`SO_ERROR`) instead, so that
+    /// the caller can short-circuit rather than attempt the doomed operation. Note that the error is cleared
+    /// in the process.
+    ///
+    /// Consulting `SO_ERROR` costs a syscall on each completion. It is necessary, because the exceptional conditions
+    /// reported by `select` (which the reactor watches for all armed file descriptors) are platform-specific:
+    /// Linux for example only reports urgent data there, and reports socket errors as read and write readiness.
+    ///
+    /// For I/O handles which are not sockets, this is the same as [`readable()`][`Async::readable()`].
+    pub async fn readable_or_error(&self) -> io::Result<()> {
+        self.readable().await?;
+
+        pending_error(self.as_fd())
+    }
+
+    /// Waits until the I/O handle is writable, failing with the pending socket error, if any.
+    ///
+    /// See [`readable_or_error()`][`Async::readable_or_error()`] for the details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// // Fails with e.g. `ConnectionReset`, rather than attempting the write
+    /// stream.writable_or_error().await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn writable_or_error(&self) -> io::Result<()> {
+        self.writable().await?;
+
+        pending_error(self.as_fd())
     }
 
     /// Polls the I/O handle for readability.
@@ -378,6 +444,57 @@ impl<T: AsFd> Async<T> {
         }
     }
 
+    /// Stops watching the I/O handle for the provided events, without dropping it.
+    ///
+    /// The wakers (and callbacks) registered for these events are removed immediately, rather than on
+    /// the next reactor pass, so they are not woken up spuriously. Pending
+    /// [`readable()`][`Async::readable()`]/[`writable()`][`Async::writable()`] futures for these events
+    /// are therefore not woken up anymore, unless polled again.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::{Async, Event};
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    ///
+    /// socket.clear_readiness(Event::Read | Event::Write)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn clear_readiness(&self, interest: EnumSet<Event>) -> io::Result<()> {
+        REACTOR.clear(self.as_fd().as_raw_fd(), interest)
+    }
+
+    /// Connects the (not yet connected) socket to a remote address provided as a raw `sockaddr` structure.
+    ///
+    /// This allows connecting sockets of address families which are not modeled by [`std::net`], like
+    /// `AF_PACKET` or `AF_VSOCK`. The method issues a non-blocking `connect`, waits for the socket to become
+    /// writable and then checks `SO_ERROR` for the outcome of the connection attempt.
+    ///
+    /// The caller is responsible for providing a correctly-formed `sockaddr` structure for the address family
+    /// of the socket. As with all other [`Async`] methods, the socket must be registered in the reactor, which
+    /// is the case when it is created with [`Async::new()`] or [`Async::new_nonblocking()`].
+    pub async fn connect_raw(&self, sockaddr: &[u8]) -> io::Result<()> {
+        syscall_los_eagain!(unsafe {
+            sys::connect(
+                self.as_fd().as_raw_fd(),
+                sockaddr.as_ptr() as *const _,
+                sockaddr.len() as _,
+            )
+        })?;
+
+        // The socket becomes writable when connected.
+        self.writable().await?;
+
+        // Check if there was an error while connecting.
+        match getsockopt::<sys::c_int>(self.as_fd(), sys::SOL_SOCKET, sys::SO_ERROR)? {
+            0 => Ok(()),
+            err => Err(io::Error::from_raw_os_error(err)),
+        }
+    }
+
     /// Performs a read operation asynchronously.
     ///
     /// The I/O handle is registered in the reactor and put in non-blocking mode. This method
@@ -472,7 +589,7 @@ impl<T: AsFd> Async<T> {
     ///
     /// # futures_lite::future::block_on(async {
     /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
-    /// socket.get_ref().connect("127.0.0.1:9000")?;
+    /// socket.connect(([127, 0, 0, 1], 9000))?;
     ///
     /// let msg = b"hello";
     /// let len = socket.write_with(|s| s.send(msg)).await?;
@@ -511,7 +628,7 @@ impl<T: AsFd> Async<T> {
     ///
     /// # futures_lite::future::block_on(async {
     /// let mut socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
-    /// socket.get_ref().connect("127.0.0.1:9000")?;
+    /// socket.connect(([127, 0, 0, 1], 9000))?;
     ///
     /// let msg = b"hello";
     /// let len = unsafe { socket.write_with_mut(|s| s.send(msg)).await? };
@@ -534,6 +651,76 @@ impl<T: AsFd> Async<T> {
     }
 }
 
+impl<T: AsFd> Async<T>
+where
+    for<'a> &'a T: Read,
+{
+    /// Reads some bytes from the I/O handle, within a caller-held budget of consecutive reads, so that a handle
+    /// which is always ready (e.g. a socket with a fast local peer) cannot monopolize the executor.
+    ///
+    /// Each read which completes right away (i.e. without waiting for the handle to become readable) consumes
+    /// one unit of the budget. Once the budget is exhausted, each read is preceded by a reactor round-trip
+    /// (see [`Reactor::yield_now`](crate::Reactor::yield_now)), which gives the other tasks of the executor,
+    /// as well as the reactor, a chance to run. The caller decides when to refill the budget, e.g. per request
+    /// of a protocol, or once per batch of work. This is the cooperative budgeting of Tokio, adapted to this reactor.
+    ///
+    /// The round-trip is done before - and not after - the read, so cancelling the returned future while it yields
+    /// does not lose any data, as nothing has been read yet.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    ///
+    /// loop {
+    ///     // At most 16 reads in a row without yielding, per batch
+    ///     let mut budget = 16;
+    ///
+    ///     for _ in 0..64 {
+    ///         let len = stream.read_budgeted(&mut buf, &mut budget).await?;
+    ///         if len == 0 {
+    ///             return Ok(());
+    ///         }
+    ///     }
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn read_budgeted(&self, buf: &mut [u8], budget: &mut u32) -> io::Result<usize> {
+        if *budget == 0 {
+            REACTOR.yield_now().await?;
+        }
+
+        let fd = self.as_fd().as_raw_fd();
+
+        REACTOR.fetch(fd, Event::Read)?;
+
+        let mut waited = false;
+
+        loop {
+            match self.get_ref().read(buf) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                res => {
+                    if !waited {
+                        *budget = budget.saturating_sub(1);
+                    }
+
+                    return res;
+                }
+            }
+
+            waited = true;
+
+            optimistic(self.readable()).await?;
+        }
+    }
+}
+
 impl<T: AsFd> AsRef<T> for Async<T> {
     fn as_ref(&self) -> &T {
         self.io.as_ref().unwrap()
@@ -542,8 +729,12 @@ impl<T: AsFd> AsRef<T> for Async<T> {
 
 impl<T: AsFd> Drop for Async<T> {
     fn drop(&mut self) {
-        if let Some(io) = &self.io {
+        if let Some(io) = self.io.take() {
+            // Deregister (which waits for the reactor to acknowledge it) strictly before `io` closes the file
+            // descriptor, so that the reactor never watches a closed - or worse, a recycled - file descriptor
             REACTOR.deregister(io.as_fd().as_raw_fd()).ok();
+
+            drop(io);
         }
     }
 }
@@ -820,6 +1011,26 @@ impl Async<TcpListener> {
         Async::new(TcpListener::bind(addr)?)
     }
 
+    /// Returns a builder for a TCP listener, which allows setting socket options which need to be applied
+    /// before the listener starts listening.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io_mini::Async;
+    /// use std::net::TcpListener;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let listener = Async::<TcpListener>::builder()
+    ///     .reuse_address(true)
+    ///     .backlog(16)
+    ///     .bind(([127, 0, 0, 1], 0))?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn builder() -> TcpListenerBuilder {
+        TcpListenerBuilder::new()
+    }
+
     /// Accepts a new incoming TCP connection.
     ///
     /// When a connection is established, it will be returned as a TCP stream together with its
@@ -838,8 +1049,40 @@ impl Async<TcpListener> {
     /// # std::io::Result::Ok(()) });
     /// ```
     pub async fn accept(&self) -> io::Result<(Async<TcpStream>, SocketAddr)> {
-        let (stream, addr) = self.read_with(|io| io.accept()).await?;
-        Ok((Async::new(stream)?, addr))
+        let (stream, addr) = self.read_with(accept).await?;
+        Ok((Async::new_nonblocking(stream)?, addr))
+    }
+
+    /// Accepts a new incoming TCP connection, if there is one pending.
+    ///
+    /// Unlike [`accept()`][`Async::accept()`], this method performs a single non-blocking
+    /// `accept` call and returns `Ok(None)` if there is no pending connection, rather than waiting for one.
+    ///
+    /// This is useful when the readiness of the listener is already known (i.e. from a custom poll loop)
+    /// and the pending connections should be drained eagerly.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpListener;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// listener.readable().await?;
+    ///
+    /// while let Some((stream, addr)) = listener.accept_now()? {
+    ///     println!("Accepted client: {}", addr);
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn accept_now(&self) -> io::Result<Option<(Async<TcpStream>, SocketAddr)>> {
+        match accept(self.get_ref()) {
+            Ok((stream, addr)) => Ok(Some((Async::new_nonblocking(stream)?, addr))),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
+        }
     }
 
     /// Returns a stream of incoming TCP connections.
@@ -875,6 +1118,121 @@ impl Async<TcpListener> {
     }
 }
 
+/// A builder for [`Async<TcpListener>`], returned by [`Async::<TcpListener>::builder()`].
+#[derive(Debug, Clone)]
+pub struct TcpListenerBuilder {
+    reuse_address: bool,
+    reuse_port: bool,
+    backlog: u32,
+}
+
+impl TcpListenerBuilder {
+    const fn new() -> Self {
+        Self {
+            reuse_address: false,
+            reuse_port: false,
+            backlog: 128,
+        }
+    }
+
+    /// Sets the `SO_REUSEADDR` option on the listener socket. Off by default.
+    pub fn reuse_address(mut self, reuse_address: bool) -> Self {
+        self.reuse_address = reuse_address;
+        self
+    }
+
+    /// Sets the `SO_REUSEPORT` option on the listener socket. Off by default.
+    ///
+    /// This allows multiple listeners to be bound to the same address, with the incoming connections
+    /// being load-balanced between them by the OS (on Linux).
+    pub fn reuse_port(mut self, reuse_port: bool) -> Self {
+        self.reuse_port = reuse_port;
+        self
+    }
+
+    /// Sets the maximum length of the queue of pending connections. 128 by default.
+    pub fn backlog(mut self, backlog: u32) -> Self {
+        self.backlog = backlog;
+        self
+    }
+
+    /// Creates the listener socket, applies the options, binds it to the specified address,
+    /// starts listening and registers the listener in the reactor.
+    pub fn bind<A: Into<SocketAddr>>(self, addr: A) -> io::Result<Async<TcpListener>> {
+        let addr = addr.into();
+
+        let domain = match addr {
+            SocketAddr::V4(_) => sys::AF_INET,
+            SocketAddr::V6(_) => sys::AF_INET6,
+        };
+
+        // Created close-on-exec, like the sockets of std, so that the listener does not leak into child processes
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let socket = unsafe {
+            OwnedFd::from_raw_fd(syscall_los!(sys::socket(
+                domain,
+                sys::SOCK_STREAM | sys::SOCK_NONBLOCK | sys::SOCK_CLOEXEC,
+                0
+            ))?)
+        };
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let socket = {
+            let socket = unsafe {
+                OwnedFd::from_raw_fd(syscall_los!(sys::socket(domain, sys::SOCK_STREAM, 0))?)
+            };
+
+            set_nonblocking(socket.as_fd())?;
+
+            // ESP-IDF has no `exec`
+            #[cfg(not(target_os = "espidf"))]
+            syscall_los!(unsafe { sys::fcntl(socket.as_raw_fd(), sys::F_SETFD, sys::FD_CLOEXEC) })?;
+
+            socket
+        };
+
+        if self.reuse_address {
+            setsockopt(
+                socket.as_fd(),
+                sys::SOL_SOCKET,
+                sys::SO_REUSEADDR,
+                1 as sys::c_int,
+            )?;
+        }
+
+        if self.reuse_port {
+            setsockopt(
+                socket.as_fd(),
+                sys::SOL_SOCKET,
+                sys::SO_REUSEPORT,
+                1 as sys::c_int,
+            )?;
+        }
+
+        let (addr, addr_len) = sockaddr(addr);
+
+        syscall_los!(unsafe {
+            sys::bind(socket.as_raw_fd(), &addr as *const _ as *const _, addr_len)
+        })?;
+
+        syscall_los!(unsafe {
+            sys::listen(
+                socket.as_raw_fd(),
+                self.backlog.min(sys::c_int::MAX as u32) as _,
+            )
+        })?;
+
+        // Use new_nonblocking because the socket is already in non-blocking mode.
+        Async::new_nonblocking(TcpListener::from(socket))
+    }
+}
+
+impl Default for TcpListenerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TryFrom<std::net::TcpListener> for Async<std::net::TcpListener> {
     type Error = io::Error;
 
@@ -898,8 +1256,55 @@ impl Async<TcpStream> {
     /// # std::io::Result::Ok(()) });
     /// ```
     pub async fn connect<A: Into<SocketAddr>>(addr: A) -> io::Result<Async<TcpStream>> {
+        Self::connect_until(addr.into(), None).await
+    }
+
+    /// Creates a TCP connection to the first of the provided addresses which accepts it.
+    ///
+    /// The addresses are tried in sequence (e.g. in the order returned by DNS), each one with its own
+    /// timeout. Returns the first successful connection, or the error of the last attempt if all of them
+    /// fail. Timed-out attempts fail with an error of kind [`io::ErrorKind::TimedOut`].
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if `addrs` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use core::time::Duration;
+    /// use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let addrs = "example.com:80".to_socket_addrs()?.collect::<Vec<SocketAddr>>();
+    /// let stream = Async::<TcpStream>::connect_any(&addrs, Duration::from_secs(3)).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn connect_any(
+        addrs: &[SocketAddr],
+        per_attempt_timeout: core::time::Duration,
+    ) -> io::Result<Async<TcpStream>> {
+        let mut last_err = None;
+
+        for addr in addrs {
+            // A timeout too large to be represented (e.g. `Duration::MAX`) means no timeout
+            let deadline = std::time::Instant::now().checked_add(per_attempt_timeout);
+
+            match Self::connect_until(*addr, deadline).await {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to")
+        }))
+    }
+
+    async fn connect_until(
+        addr: SocketAddr,
+        deadline: Option<std::time::Instant>,
+    ) -> io::Result<Async<TcpStream>> {
         // Figure out how to handle this address.
-        let addr = addr.into();
 
         let socket = match addr {
             SocketAddr::V4(v4) => {
@@ -949,7 +1354,9 @@ impl Async<TcpStream> {
         let stream = Async::new_nonblocking(TcpStream::from(socket))?;
 
         // The stream becomes writable when connected.
-        stream.writable().await?;
+        REACTOR
+            .ready(stream.as_fd().as_raw_fd(), Event::Write.into(), deadline)
+            .await?;
 
         // Check if there was an error while connecting.
         match stream.get_ref().take_error()? {
@@ -958,6 +1365,55 @@ impl Async<TcpStream> {
         }
     }
 
+    /// Returns a builder for TCP connections, which - unlike [`connect()`][`Async::<TcpStream>::connect()`] -
+    /// disables Nagle's algorithm (i.e. sets `TCP_NODELAY`) by default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::builder()
+    ///     .connect(([127, 0, 0, 1], 8000))
+    ///     .await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn builder() -> TcpStreamBuilder {
+        TcpStreamBuilder::new()
+    }
+
+    /// Creates a new, independent handle to the same connection.
+    ///
+    /// The underlying file descriptor is duplicated (as with [`TcpStream::try_clone`]) and the duplicate
+    /// is registered in the reactor on its own. Unlike the halves of a split stream, both handles can read and
+    /// write, and can be moved to different parts of the code. Dropping one of them deregisters and closes only
+    /// its own file descriptor; the connection stays open until both are dropped (or it is shut down).
+    ///
+    /// Note that both handles share the connection, so concurrent reads (or writes) of the two interleave at
+    /// arbitrary points. They also share the file status flags of the connection, including the non-blocking mode,
+    /// so neither of them may be switched to blocking mode.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use futures_lite::io::AsyncWriteExt;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let mut heartbeat = stream.try_clone()?;
+    /// heartbeat.write_all(b"ping").await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn try_clone(&self) -> io::Result<Async<TcpStream>> {
+        // The duplicate shares the non-blocking mode of the original
+        Async::new_nonblocking(self.get_ref().try_clone()?)
+    }
+
     /// Reads data from the stream without removing it from the buffer.
     ///
     /// Returns the number of bytes read. Successive calls of this method read the same data.
@@ -984,88 +1440,1334 @@ impl Async<TcpStream> {
     pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
         self.read_with(|io| io.peek(buf)).await
     }
-}
-
-impl TryFrom<std::net::TcpStream> for Async<std::net::TcpStream> {
-    type Error = io::Error;
-
-    fn try_from(stream: std::net::TcpStream) -> io::Result<Self> {
-        Async::new(stream)
-    }
-}
 
-impl Async<UdpSocket> {
-    /// Creates a UDP socket bound to the specified address.
+    /// Polls the stream for read readiness, like [`poll_readable()`][`Async::poll_readable()`], but resolves
+    /// to a [`Ready`] description of the readiness rather than to `()`.
     ///
-    /// Binding with port number 0 will request an available port from the OS.
+    /// This eases porting code structured around the readiness model of `mio`. Once the stream is readable,
+    /// the description is completed without reading from the stream:
+    /// - whether the stream is also known to be writable (without consuming that readiness);
+    /// - whether the peer has closed its write half (i.e. reading would return EOF), by peeking at the stream;
+    /// - whether the write half of the stream is closed (i.e. writing would fail with
+    ///   [`io::ErrorKind::BrokenPipe`]), by sending zero bytes.
+    ///
+    /// If the connection is gone altogether (e.g. reset by the peer), the pending error of the stream is returned
+    /// instead.
+    ///
+    /// As with `mio`, the readiness might be spurious, so the subsequent read might still fail with
+    /// [`io::ErrorKind::WouldBlock`].
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
     /// use async_io_mini::Async;
-    /// use std::net::UdpSocket;
+    /// use futures_lite::future;
+    /// use std::net::TcpStream;
     ///
     /// # futures_lite::future::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
-    /// println!("Bound to {}", socket.get_ref().local_addr()?);
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let ready = future::poll_fn(|cx| stream.poll_read_ready(cx)).await?;
+    ///
+    /// if ready.is_read_closed() {
+    ///     println!("The peer is done sending");
+    /// }
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub fn bind<A: Into<SocketAddr>>(addr: A) -> io::Result<Async<UdpSocket>> {
-        let addr = addr.into();
-        Async::new(UdpSocket::bind(addr)?)
+    pub fn poll_read_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<Ready>> {
+        ready!(self.poll_readable(cx))?;
+
+        let fd = self.as_fd().as_raw_fd();
+
+        let writable = REACTOR.is_ready(fd, Event::Write)?;
+
+        let mut byte = 0_u8;
+
+        let read_closed = match syscall_los!(unsafe {
+            sys::recv(fd, &mut byte as *mut u8 as *mut _, 1, sys::MSG_PEEK)
+        }) {
+            Ok(len) => len == 0,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                ) =>
+            {
+                false
+            }
+            // The peek consumed the pending socket error (e.g. `ECONNRESET`), so it must not be lost
+            Err(err) => return Poll::Ready(Err(err)),
+        };
+
+        // Std sets `SO_NOSIGPIPE` on its sockets on Apple platforms, which lack `MSG_NOSIGNAL`
+        #[cfg(not(target_vendor = "apple"))]
+        let flags = sys::MSG_DONTWAIT | sys::MSG_NOSIGNAL;
+        #[cfg(target_vendor = "apple")]
+        let flags = sys::MSG_DONTWAIT;
+
+        // A zero-length send transmits nothing, but fails once the write half is shut down
+        let write_closed = match syscall_los!(unsafe {
+            sys::send(fd, &byte as *const u8 as *const _, 0, flags)
+        }) {
+            Ok(_) => false,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::BrokenPipe | io::ErrorKind::NotConnected
+                ) =>
+            {
+                true
+            }
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                ) =>
+            {
+                false
+            }
+            Err(err) => return Poll::Ready(Err(err)),
+        };
+
+        Poll::Ready(Ok(Ready {
+            readable: true,
+            writable,
+            read_closed,
+            write_closed,
+        }))
     }
 
-    /// Receives a single datagram message.
-    ///
-    /// Returns the number of bytes read and the address the message came from.
+    /// Reads some bytes from the stream into an uninitialized buffer, sparing the zero-filling which reading
+    /// into a `&mut [u8]` buffer requires (a significant cost for large buffers on slow MCUs).
     ///
-    /// This method must be called with a valid byte slice of sufficient size to hold the message.
-    /// If the message is too long to fit, excess bytes may get discarded.
+    /// Returns the number of bytes read, where `Ok(0)` means that the peer has closed the stream (EOF).
+    /// Only that many bytes at the start of `buf` are initialized; the rest of `buf` is left untouched.
     ///
     /// # Examples
     ///
     /// ```no_run
+    /// use core::mem::MaybeUninit;
+    ///
     /// use async_io_mini::Async;
-    /// use std::net::UdpSocket;
+    /// use std::net::TcpStream;
     ///
     /// # futures_lite::future::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
     ///
-    /// let mut buf = [0u8; 1024];
-    /// let (len, addr) = socket.recv_from(&mut buf).await?;
+    /// let mut buf = Vec::<u8>::with_capacity(16384);
+    /// let len = stream.read_uninit(buf.spare_capacity_mut()).await?;
+    ///
+    /// // Safety: `read_uninit` initialized the first `len` bytes of the spare capacity
+    /// unsafe { buf.set_len(len) };
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        self.read_with(|io| io.recv_from(buf)).await
+    pub async fn read_uninit(&self, buf: &mut [core::mem::MaybeUninit<u8>]) -> io::Result<usize> {
+        self.read_with(|io| {
+            // The kernel only ever writes to the buffer, so passing uninitialized memory is sound
+            let len = syscall_los!(unsafe {
+                sys::recv(io.as_raw_fd(), buf.as_mut_ptr() as *mut _, buf.len(), 0)
+            })?;
+
+            Ok(len as usize)
+        })
+        .await
     }
 
-    /// Receives a single datagram message without removing it from the queue.
+    /// Reads all bytes until EOF, appending them to `buf` and growing it as necessary, up to a limit.
     ///
-    /// Returns the number of bytes read and the address the message came from.
+    /// Returns the number of bytes read. Unlike `AsyncReadExt::read_to_end` of `futures-lite`, this method fails
+    /// with an error of kind [`io::ErrorKind::Other`] once the stream has delivered more than `limit` bytes,
+    /// so that a hostile or buggy peer cannot make the buffer grow unbounded. In that case, as well as on any other
+    /// error, `buf` holds the bytes read so far.
     ///
-    /// This method must be called with a valid byte slice of sufficient size to hold the message.
-    /// If the message is too long to fit, excess bytes may get discarded.
+    /// The spare capacity of `buf` is read into without zero-filling it (see
+    /// [`read_uninit()`][`Async::<TcpStream>::read_uninit()`]).
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use async_io_mini::Async;
-    /// use std::net::UdpSocket;
+    /// use std::net::TcpStream;
     ///
     /// # futures_lite::future::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
     ///
-    /// let mut buf = [0u8; 1024];
-    /// let (len, addr) = socket.peek_from(&mut buf).await?;
+    /// let mut response = Vec::new();
+    /// stream.read_to_end_with_limit(&mut response, 16384).await?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        self.read_with(|io| io.peek_from(buf)).await
+    pub async fn read_to_end_with_limit(
+        &self,
+        buf: &mut Vec<u8>,
+        limit: usize,
+    ) -> io::Result<usize> {
+        const CHUNK: usize = 1024;
+
+        let start = buf.len();
+
+        loop {
+            let read = buf.len() - start;
+
+            // Reading (at least) one byte over the limit tells a stream of exactly `limit` bytes from a longer one
+            let wanted = CHUNK.min(limit.saturating_sub(read).saturating_add(1));
+            buf.reserve(wanted);
+
+            let spare = buf.spare_capacity_mut();
+            let len = self.read_uninit(&mut spare[..wanted]).await?;
+
+            if len == 0 {
+                return Ok(read);
+            }
+
+            // Safety: `read_uninit` initialized the first `len` bytes of the spare capacity
+            unsafe { buf.set_len(buf.len() + len) };
+
+            if read + len > limit {
+                Err(io::Error::other("the stream exceeds the read limit"))?;
+            }
+        }
     }
 
-    /// Sends data to the specified address.
+    /// Reads some bytes from the stream into a buffer acquired from the pool.
     ///
-    /// Returns the number of bytes writen.
+    /// Returns the buffer, with its length set to the number of bytes read, where an empty buffer means that
+    /// the peer has closed the stream (EOF). The buffer is acquired before waiting for the stream to become
+    /// readable, and goes back to the pool once it (or the [`PooledBuf`] holding it) is dropped, as
+    /// implemented by the pool.
+    ///
+    /// If the pool is exhausted, fails immediately with an error of kind [`io::ErrorKind::OutOfMemory`],
+    /// without reading from the stream. This error is recoverable: the read can be retried once some of
+    /// the buffers are released.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::cell::RefCell;
+    ///
+    /// use async_io_mini::{Async, BufferPool};
+    /// use std::net::TcpStream;
+    ///
+    /// struct Pool(RefCell<Vec<Box<[u8]>>>);
+    ///
+    /// impl BufferPool for Pool {
+    ///     type Buf = Box<[u8]>;
+    ///
+    ///     fn acquire(&self) -> Option<Self::Buf> {
+    ///         self.0.borrow_mut().pop()
+    ///     }
+    /// }
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let pool = Pool(RefCell::new(vec![vec![0; 1500].into_boxed_slice(); 4]));
+    ///
+    /// let buf = stream.read_pooled(&pool).await?;
+    /// println!("Read {} bytes", buf.len());
+    ///
+    /// // Hand the buffer back to the pool
+    /// pool.0.borrow_mut().push(buf.into_inner());
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn read_pooled<B: BufferPool + ?Sized>(
+        &self,
+        pool: &B,
+    ) -> io::Result<PooledBuf<B::Buf>> {
+        let Some(mut buf) = pool.acquire() else {
+            Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                "the buffer pool is exhausted",
+            ))?
+        };
+
+        let len = self.read_with(|mut io| io.read(buf.as_mut())).await?;
+
+        Ok(PooledBuf { buf, len })
+    }
+
+    /// Reads a message made of a fixed-size header followed by a variable-size body, with a single readiness wait
+    /// for both.
+    ///
+    /// Once the stream is readable, `header` is filled first. Once it is complete, `body_len` is called with it
+    /// to tell the length of the body, and as much of the body as the OS has already buffered is read into `body`
+    /// (up to that length, capped to the length of `body`), without waiting for readability again. For small
+    /// messages on a fast link, this typically halves the reactor round-trips per message, compared to reading
+    /// the header and the body separately.
+    ///
+    /// Returns how many bytes of `header` and of `body` were filled, where `(0, 0)` means that the peer has
+    /// closed the stream (EOF). Either of them might be filled only partially, if the OS has not buffered all
+    /// of the message yet (or on EOF), in which case the rest can be read e.g. with
+    /// [`recv_exact()`][`Async::<TcpStream>::recv_exact()`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let mut header = [0; 2];
+    /// let mut body = [0; 1024];
+    ///
+    /// let (header_len, body_len) = stream
+    ///     .read_header_body(&mut header, &mut body, |header| {
+    ///         u16::from_be_bytes([header[0], header[1]]) as usize
+    ///     })
+    ///     .await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn read_header_body<F>(
+        &self,
+        header: &mut [u8],
+        body: &mut [u8],
+        body_len: F,
+    ) -> io::Result<(usize, usize)>
+    where
+        F: FnOnce(&[u8]) -> usize,
+    {
+        let mut body_len = Some(body_len);
+
+        self.read_with(|mut io| {
+            let mut header_read = 0;
+
+            while header_read < header.len() {
+                match io.read(&mut header[header_read..]) {
+                    Ok(0) => return Ok((header_read, 0)),
+                    Ok(len) => header_read += len,
+                    // Whatever was read must be returned, rather than waiting for the rest of the header
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock && header_read > 0 => {
+                        return Ok((header_read, 0))
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+
+            let wanted = body_len
+                .take()
+                .map_or(0, |body_len| body_len(header))
+                .min(body.len());
+
+            let mut body_read = 0;
+
+            while body_read < wanted {
+                match io.read(&mut body[body_read..wanted]) {
+                    Ok(0) => break,
+                    Ok(len) => body_read += len,
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(err) => return Err(err),
+                }
+            }
+
+            Ok((header_read, body_read))
+        })
+        .await
+    }
+
+    /// Receives the exact number of bytes required to fill `buf`.
+    ///
+    /// Each `recv` call copies as many of the remaining bytes as the OS has buffered, and the stream only waits
+    /// for readability when a call comes back short. Note that `MSG_WAITALL` is of no help here: on a
+    /// non-blocking socket, the OS returns whatever it has buffered regardless of it.
+    ///
+    /// Unlike `AsyncReadExt::read_exact`, this method takes `&self`. Returns an error of kind
+    /// [`io::ErrorKind::UnexpectedEof`] if the stream is closed before `buf` is filled. In that case, as well as
+    /// on any other error, the contents of `buf` are unspecified.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let mut record = [0u8; 64];
+    /// stream.recv_exact(&mut record).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_exact(&self, buf: &mut [u8]) -> io::Result<()> {
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let len = self.read_with(|mut io| io.read(&mut buf[offset..])).await?;
+
+            if len == 0 {
+                Err(io::ErrorKind::UnexpectedEof)?;
+            }
+
+            offset += len;
+        }
+
+        Ok(())
+    }
+
+    /// Reads some bytes from the stream, waiting for data to arrive no later than the provided deadline.
+    ///
+    /// Returns the number of bytes read, where `Ok(0)` means that the peer has closed the stream (EOF).
+    /// If no data arrives before the deadline, an error of kind [`io::ErrorKind::TimedOut`] is returned
+    /// instead.
+    ///
+    /// The method returns as soon as some data is read and does not wait for `buf` to be filled.
+    ///
+    /// The deadline is enforced by the reactor (see [`Reactor::ready`](crate::Reactor::ready)), so no timer is
+    /// necessary.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpStream;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let deadline = Instant::now() + Duration::from_millis(50);
+    ///
+    /// match stream.read_until_deadline(&mut buf, deadline).await {
+    ///     Ok(0) => println!("EOF"),
+    ///     Ok(len) => println!("Read {len} bytes"),
+    ///     Err(err) if err.kind() == std::io::ErrorKind::TimedOut => println!("No data yet"),
+    ///     Err(err) => Err(err)?,
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn read_until_deadline(
+        &self,
+        buf: &mut [u8],
+        deadline: std::time::Instant,
+    ) -> io::Result<usize> {
+        let fd = self.as_fd().as_raw_fd();
+
+        REACTOR.fetch(fd, Event::Read)?;
+
+        loop {
+            match self.get_ref().read(buf) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                res => return res,
+            }
+
+            REACTOR
+                .ready(fd, Event::Read.into(), Some(deadline))
+                .await?;
+        }
+    }
+
+    /// Reads some bytes from the stream, unless the token is cancelled first.
+    ///
+    /// Returns the number of bytes read, or an error of kind [`io::ErrorKind::Interrupted`] if the token is
+    /// (or already was) cancelled while waiting for the stream to become readable. No data is lost on
+    /// cancellation, as the wait is aborted only before the read happens.
+    pub async fn read_cancellable(
+        &self,
+        buf: &mut [u8],
+        token: &crate::CancelToken,
+    ) -> io::Result<usize> {
+        token.run(self.read_with(|mut io| io.read(buf))).await
+    }
+
+    /// Writes some bytes to the stream, unless the token is cancelled first.
+    ///
+    /// Returns the number of bytes written, or an error of kind [`io::ErrorKind::Interrupted`] if the token is
+    /// (or already was) cancelled while waiting for the stream to become writable.
+    pub async fn write_cancellable(
+        &self,
+        buf: &[u8],
+        token: &crate::CancelToken,
+    ) -> io::Result<usize> {
+        token.run(self.write_with(|mut io| io.write(buf))).await
+    }
+
+    /// Sets the `TCP_NODELAY` option on the stream, which disables Nagle's algorithm when `true`.
+    ///
+    /// Disabling Nagle's algorithm avoids the latency caused by its interaction with delayed ACKs for small
+    /// interactive messages (e.g. request/response protocols).
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.get_ref().set_nodelay(nodelay)
+    }
+
+    /// Gets the value of the `TCP_NODELAY` option on the stream.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.get_ref().nodelay()
+    }
+
+    /// Sets the `TCP_QUICKACK` option on the stream, which - when `true` - makes the stream send ACKs immediately,
+    /// rather than delaying them.
+    ///
+    /// Note that the option is not sticky: the kernel may switch back to delayed ACKs on its own afterwards,
+    /// so latency-sensitive code might need to set it again, e.g. after each read.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_quickack(&self, quickack: bool) -> io::Result<()> {
+        setsockopt(
+            self.as_fd(),
+            sys::IPPROTO_TCP,
+            sys::TCP_QUICKACK,
+            quickack as sys::c_int,
+        )
+    }
+
+    /// Gets the value of the `TCP_QUICKACK` option on the stream.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn quickack(&self) -> io::Result<bool> {
+        getsockopt::<sys::c_int>(self.as_fd(), sys::IPPROTO_TCP, sys::TCP_QUICKACK)
+            .map(|quickack| quickack != 0)
+    }
+
+    /// Sets the congestion control algorithm of the stream (e.g. `"bbr"` or `"cubic"`), i.e. the `TCP_CONGESTION`
+    /// option.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] if the algorithm is not available in the kernel
+    /// (see `/proc/sys/net/ipv4/tcp_available_congestion_control`; the module of the algorithm might need to be
+    /// loaded first), and of kind [`io::ErrorKind::PermissionDenied`] if the process is not allowed to use it
+    /// (see `/proc/sys/net/ipv4/tcp_allowed_congestion_control`).
+    ///
+    /// Only available on Linux and Android; the lwIP stack of ESP-IDF has a single, built-in algorithm.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_congestion(&self, algo: &str) -> io::Result<()> {
+        let result = syscall_los!(unsafe {
+            sys::setsockopt(
+                self.as_fd().as_raw_fd(),
+                sys::IPPROTO_TCP,
+                sys::TCP_CONGESTION,
+                algo.as_ptr() as *const _,
+                algo.len() as _,
+            )
+        });
+
+        match result {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("the congestion control algorithm `{algo}` is not available"),
+            )),
+            result => result.map(|_| ()),
+        }
+    }
+
+    /// Gets the congestion control algorithm of the stream, i.e. the value of the `TCP_CONGESTION` option.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn congestion(&self) -> io::Result<String> {
+        // `TCP_CA_NAME_MAX`
+        let mut name = [0_u8; 16];
+        let mut len = name.len() as sys::socklen_t;
+
+        syscall_los!(unsafe {
+            sys::getsockopt(
+                self.as_fd().as_raw_fd(),
+                sys::IPPROTO_TCP,
+                sys::TCP_CONGESTION,
+                name.as_mut_ptr() as *mut _,
+                &mut len,
+            )
+        })?;
+
+        let name = &name[..len as usize];
+        let name = name.split(|byte| *byte == 0).next().unwrap_or_default();
+
+        Ok(String::from_utf8_lossy(name).into_owned())
+    }
+
+    /// Corks the stream, i.e. sets `TCP_CORK` (Linux) or `TCP_NOPUSH` (BSDs and macOS) on it.
+    ///
+    /// While corked, the OS queues the written data rather than sending out partial segments, so a sequence of
+    /// small writes (e.g. a header followed by a body) is coalesced into as few segments as possible, without
+    /// having to buffer the writes in user space.
+    ///
+    /// The queued data is only flushed once the stream is [uncorked](Async::<TcpStream>::uncork) (on Linux, also
+    /// after a 200ms timeout), so every `cork` must be followed by an `uncork`. Prefer
+    /// [`corked`](Async::<TcpStream>::corked), which guarantees that.
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    ))]
+    pub fn cork(&self) -> io::Result<()> {
+        set_cork(self.as_fd(), true)
+    }
+
+    /// Uncorks the stream, flushing the data queued since the stream was [corked](Async::<TcpStream>::cork).
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    ))]
+    pub fn uncork(&self) -> io::Result<()> {
+        set_cork(self.as_fd(), false)
+    }
+
+    /// Runs `f` with the stream [corked](Async::<TcpStream>::cork), so that the writes done by `f` are coalesced
+    /// into as few segments as possible, and then uncorks the stream.
+    ///
+    /// The stream is uncorked even if `f` fails, or if the returned future is dropped before completion.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use futures_lite::AsyncWriteExt;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// stream
+    ///     .corked(|mut stream| async move {
+    ///         stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n").await?;
+    ///         stream.write_all(b"hello").await
+    ///     })
+    ///     .await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    ))]
+    pub async fn corked<'a, F, Fut, R>(&'a self, f: F) -> io::Result<R>
+    where
+        F: FnOnce(&'a Self) -> Fut,
+        Fut: Future<Output = io::Result<R>>,
+    {
+        struct Cork<'a>(Option<BorrowedFd<'a>>);
+
+        impl Cork<'_> {
+            fn release(mut self) -> io::Result<()> {
+                set_cork(self.0.take().unwrap(), false)
+            }
+        }
+
+        impl Drop for Cork<'_> {
+            fn drop(&mut self) {
+                if let Some(fd) = self.0.take() {
+                    let _ = set_cork(fd, false);
+                }
+            }
+        }
+
+        self.cork()?;
+
+        let cork = Cork(Some(self.as_fd()));
+
+        let result = f(self).await?;
+
+        cork.release()?;
+
+        Ok(result)
+    }
+
+    /// Checks - without sending or consuming any application data - whether the connection is still alive.
+    ///
+    /// Returns `Ok(false)` if the connection is known to be dead, i.e. if the socket has a pending error
+    /// (like `ECONNRESET`, or `ETIMEDOUT` reported by failed TCP keepalive probes), or if the peer has closed
+    /// the connection. Returns `Ok(true)` otherwise. Note that the pending error, if any, is consumed.
+    ///
+    /// # Caveats
+    ///
+    /// This method does not generate any network traffic by itself: a zero-length `send` on a TCP socket is a no-op
+    /// on Linux and on LwIP (ESP-IDF), so it cannot be used to probe the peer. Hence, a peer which disappeared
+    /// without closing the connection (e.g. due to a power loss) is only detected once the OS has found out
+    /// about it - either by failing to deliver previously written data, or via TCP keepalive probes.
+    /// Enable `SO_KEEPALIVE` on the socket (and possibly tune the keepalive intervals) for the latter.
+    ///
+    /// Also note that a peer which has only shut down its writing side is reported as dead.
+    pub fn probe_liveness(&self) -> io::Result<bool> {
+        if let Some(err) = self.get_ref().take_error()? {
+            return dead_or_err(err);
+        }
+
+        let mut buf = [0u8; 1];
+
+        let result = syscall_los!(unsafe {
+            sys::recv(
+                self.as_fd().as_raw_fd(),
+                buf.as_mut_ptr() as *mut _,
+                buf.len(),
+                sys::MSG_PEEK | sys::MSG_DONTWAIT,
+            )
+        });
+
+        match result {
+            Ok(0) => Ok(false),
+            Ok(_) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(true),
+            Err(err) => dead_or_err(err),
+        }
+    }
+
+    /// Writes all bytes of the provided buffers to the stream, using vectored (`writev`) writes.
+    ///
+    /// Short writes are handled by skipping the fully-written buffers and re-slicing the partially-written
+    /// one, and then waiting for the stream to become writable again.
+    ///
+    /// The `bufs` slice is modified in the process and its contents are unspecified once the method returns.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::io::IoSlice;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let header = [0u8, 5];
+    /// let payload = b"hello";
+    ///
+    /// stream
+    ///     .write_all_vectored(&mut [IoSlice::new(&header), IoSlice::new(payload)])
+    ///     .await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn write_all_vectored(&self, mut bufs: &mut [io::IoSlice<'_>]) -> io::Result<()> {
+        // Skip the leading empty buffers, if any
+        io::IoSlice::advance_slices(&mut bufs, 0);
+
+        while !bufs.is_empty() {
+            let len = self.write_with(|mut io| io.write_vectored(bufs)).await?;
+
+            if len == 0 {
+                Err(io::ErrorKind::WriteZero)?;
+            }
+
+            io::IoSlice::advance_slices(&mut bufs, len);
+        }
+
+        Ok(())
+    }
+
+    /// Writes some bytes from the owned buffer to the stream, returning the buffer back on completion.
+    ///
+    /// Returns the number of bytes written, along with the buffer, which can then be reused (e.g. returned to
+    /// a buffer pool) without reallocation. Together with an `Arc<Async<TcpStream>>`, this allows spawning
+    /// writes into detached tasks without borrowing the data from the caller.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// let (len, mut buf) = stream.write_owned(b"hello".to_vec()).await?;
+    ///
+    /// // Reuse the buffer for the next message
+    /// buf.clear();
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn write_owned(&self, buf: Vec<u8>) -> io::Result<(usize, Vec<u8>)> {
+        let len = self.write_with(|mut io| io.write(&buf)).await?;
+
+        Ok((len, buf))
+    }
+
+    /// Writes all bytes of the owned buffer to the stream, returning the buffer back on completion.
+    ///
+    /// This is the [`write_all`](futures_lite::AsyncWriteExt::write_all) counterpart of
+    /// [`write_owned()`][`Async::<TcpStream>::write_owned()`].
+    pub async fn write_all_owned(&self, buf: Vec<u8>) -> io::Result<Vec<u8>> {
+        let mut written = 0;
+
+        while written < buf.len() {
+            let len = self.write_with(|mut io| io.write(&buf[written..])).await?;
+
+            if len == 0 {
+                Err(io::ErrorKind::WriteZero)?;
+            }
+
+            written += len;
+        }
+
+        Ok(buf)
+    }
+
+    /// Waits until the stream is writable and its send buffer has room for at least `bytes` bytes.
+    ///
+    /// This is useful for pacing large writes (e.g. full packets), as [`writable()`][`Async::writable()`] completes
+    /// as soon as any room frees up. The room is the payload capacity of the send buffer - half of `SO_SNDBUF`,
+    /// which the kernel doubles for its bookkeeping overhead - minus the bytes still queued in it (`SIOCOUTQ`).
+    ///
+    /// Note that this is polling rather than readiness: the socket stays writable while the room is insufficient,
+    /// so the check is retried with a timer, with an exponential backoff (from 1ms up to 64ms). Hence, the task
+    /// keeps being woken up while waiting, and the method needs the `embassy-time` feature.
+    ///
+    /// This is best-effort, so a write of `bytes` bytes might still be partial.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if `bytes` exceeds the payload capacity of the
+    /// send buffer, as there would never be enough room.
+    #[cfg(all(
+        feature = "embassy-time",
+        any(target_os = "linux", target_os = "android")
+    ))]
+    pub async fn writable_at_least(&self, bytes: usize) -> io::Result<()> {
+        const MAX_BACKOFF: core::time::Duration = core::time::Duration::from_millis(64);
+
+        let mut backoff = core::time::Duration::from_millis(1);
+
+        loop {
+            self.writable().await?;
+
+            // `SO_SNDBUF` is reported doubled, whereas `SIOCOUTQ` counts payload bytes only
+            let size = self.send_buffer_size()? / 2;
+            if bytes > size {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "requested room exceeds the send buffer size",
+                ))?;
+            }
+
+            let mut queued: sys::c_int = 0;
+            syscall_los!(unsafe {
+                sys::ioctl(self.as_fd().as_raw_fd(), sys::TIOCOUTQ, &mut queued)
+            })?;
+
+            if size.saturating_sub(queued as usize) >= bytes {
+                return Ok(());
+            }
+
+            crate::Timer::after(backoff).await;
+
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Sets the type-of-service (`IP_TOS`) field of the outgoing packets, or the traffic class
+    /// (`IPV6_TCLASS`) for IPv6 sockets.
+    ///
+    /// This is typically used for DSCP marking, where the DSCP value occupies the upper 6 bits.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::TcpStream;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8000)).await?;
+    ///
+    /// // DSCP EF (Expedited Forwarding)
+    /// stream.set_tos(46 << 2)?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn set_tos(&self, tos: u32) -> io::Result<()> {
+        set_tos(self.as_fd(), self.get_ref().local_addr()?, tos)
+    }
+
+    /// Gets the type-of-service (`IP_TOS`) field of the outgoing packets, or the traffic class
+    /// (`IPV6_TCLASS`) for IPv6 sockets.
+    ///
+    /// For more information about this option, see [`set_tos()`][`Async::<TcpStream>::set_tos()`].
+    pub fn tos(&self) -> io::Result<u32> {
+        tos(self.as_fd(), self.get_ref().local_addr()?)
+    }
+
+    /// Sets the size of the socket send buffer (`SO_SNDBUF`), in bytes.
+    ///
+    /// Larger buffers help to keep links with a high bandwidth-delay product (e.g. satellite links) saturated.
+    /// Note that the OS may adjust the requested size (Linux e.g. doubles it for bookkeeping overhead and caps it
+    /// at `net.core.wmem_max`), so use [`send_buffer_size()`][`Async::<TcpStream>::send_buffer_size()`] to get
+    /// the effective size.
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        set_buffer_size(self.as_fd(), sys::SO_SNDBUF, size)
+    }
+
+    /// Gets the effective size of the socket send buffer (`SO_SNDBUF`), in bytes.
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        buffer_size(self.as_fd(), sys::SO_SNDBUF)
+    }
+
+    /// Sets the size of the socket receive buffer (`SO_RCVBUF`), in bytes.
+    ///
+    /// As with [`set_send_buffer_size()`][`Async::<TcpStream>::set_send_buffer_size()`], the OS may adjust
+    /// the requested size, so use [`recv_buffer_size()`][`Async::<TcpStream>::recv_buffer_size()`] to get
+    /// the effective size.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        set_buffer_size(self.as_fd(), sys::SO_RCVBUF, size)
+    }
+
+    /// Gets the effective size of the socket receive buffer (`SO_RCVBUF`), in bytes.
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        buffer_size(self.as_fd(), sys::SO_RCVBUF)
+    }
+}
+
+/// A builder for [`Async<TcpStream>`] connections, returned by [`Async::<TcpStream>::builder()`].
+#[derive(Debug, Clone)]
+pub struct TcpStreamBuilder {
+    nodelay: bool,
+}
+
+impl TcpStreamBuilder {
+    const fn new() -> Self {
+        Self { nodelay: true }
+    }
+
+    /// Sets the `TCP_NODELAY` option on the connection. On by default.
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
+
+    /// Creates a TCP connection to the specified address and applies the options.
+    pub async fn connect<A: Into<SocketAddr>>(self, addr: A) -> io::Result<Async<TcpStream>> {
+        let stream = Async::<TcpStream>::connect(addr).await?;
+
+        stream.set_nodelay(self.nodelay)?;
+
+        Ok(stream)
+    }
+}
+
+impl Default for TcpStreamBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TryFrom<std::net::TcpStream> for Async<std::net::TcpStream> {
+    type Error = io::Error;
+
+    fn try_from(stream: std::net::TcpStream) -> io::Result<Self> {
+        Async::new(stream)
+    }
+}
+
+impl Async<UdpSocket> {
+    /// Creates a UDP socket bound to the specified address.
+    ///
+    /// Binding with port number 0 will request an available port from the OS.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// println!("Bound to {}", socket.get_ref().local_addr()?);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn bind<A: Into<SocketAddr>>(addr: A) -> io::Result<Async<UdpSocket>> {
+        let addr = addr.into();
+        Async::new(UdpSocket::bind(addr)?)
+    }
+
+    /// Creates a UDP socket bound to the specified IP address and to the first free port of the range.
+    ///
+    /// The ports are tried in order, skipping those which are already in use. This is useful for protocols
+    /// (e.g. peer-to-peer or NAT traversal ones) which require the source port to be in a specific range.
+    /// The port which was actually bound is available with the `local_addr` method of the socket.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the range is empty, and of kind
+    /// [`io::ErrorKind::AddrInUse`] if all of the ports in the range are in use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind_in_range([127, 0, 0, 1], 50000..=50100)?;
+    /// println!("Bound to {}", socket.get_ref().local_addr()?);
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn bind_in_range<A: Into<IpAddr>>(
+        ip: A,
+        ports: core::ops::RangeInclusive<u16>,
+    ) -> io::Result<Async<UdpSocket>> {
+        let ip = ip.into();
+
+        if ports.is_empty() {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the port range is empty",
+            ))?;
+        }
+
+        for port in ports {
+            match UdpSocket::bind(SocketAddr::new(ip, port)) {
+                Ok(socket) => return Async::new(socket),
+                Err(err) if err.kind() == io::ErrorKind::AddrInUse => continue,
+                Err(err) => Err(err)?,
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            "all of the ports in the range are in use",
+        ))
+    }
+
+    /// Connects the UDP socket to a remote address.
+    ///
+    /// When connected, the socket only sends and receives datagrams to and from the remote address,
+    /// and [`send()`][`Async::<UdpSocket>::send()`] and [`recv()`][`Async::<UdpSocket>::recv()`]
+    /// can be used.
+    ///
+    /// Connecting also enables reporting of asynchronous errors: if the peer is down and an ICMP
+    /// "port unreachable" message arrives in response to a previously sent datagram, the socket gets
+    /// a pending error and the reactor wakes up both readers and writers. The next
+    /// [`recv()`][`Async::<UdpSocket>::recv()`] or [`send()`][`Async::<UdpSocket>::send()`] then fails
+    /// with [`io::ErrorKind::ConnectionRefused`], and clears the pending error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.connect(([127, 0, 0, 1], 9000))?;
+    ///
+    /// socket.send(b"hello").await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn connect<A: Into<SocketAddr>>(&self, addr: A) -> io::Result<()> {
+        self.get_ref().connect(addr.into())
+    }
+
+    /// Receives a single datagram message.
+    ///
+    /// Returns the number of bytes read and the address the message came from.
+    ///
+    /// This method must be called with a valid byte slice of sufficient size to hold the message.
+    /// If the message is too long to fit, excess bytes may get discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let (len, addr) = socket.recv_from(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.read_with(|io| io.recv_from(buf)).await
+    }
+
+    /// Receives a single datagram message, reporting whether it was truncated.
+    ///
+    /// Returns the length of the message, the address the message came from, and `true` if the message
+    /// was longer than `buf` (in which case the excess bytes are discarded).
+    ///
+    /// On Linux and Android, the returned length is the full length of the message - which might exceed
+    /// `buf.len()` - so the caller can resize the buffer for the next (jumbo) message. Other platforms (e.g. the BSDs
+    /// and the ESP-IDF) do not support discovering the full length, so the returned length is capped at `buf.len()`,
+    /// but the truncation is still reported.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut buf = vec![0u8; 1024];
+    /// let (len, addr, truncated) = socket.recv_from_with_trunc(&mut buf).await?;
+    ///
+    /// if truncated {
+    ///     println!("Dropped a message of {len} bytes; growing the buffer");
+    ///     buf.resize(len.max(2 * buf.len()), 0);
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_from_with_trunc(
+        &self,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr, bool)> {
+        self.read_with(|io| {
+            let mut addr = core::mem::MaybeUninit::<sys::sockaddr_storage>::zeroed();
+
+            let mut iov = sys::iovec {
+                iov_base: buf.as_mut_ptr() as *mut _,
+                iov_len: buf.len(),
+            };
+
+            let mut msg: sys::msghdr = unsafe { core::mem::zeroed() };
+            msg.msg_name = addr.as_mut_ptr() as *mut _;
+            msg.msg_namelen = core::mem::size_of::<sys::sockaddr_storage>() as _;
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+
+            // With `MSG_TRUNC`, Linux returns the full length of the message rather than the copied one
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let flags = sys::MSG_TRUNC;
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            let flags = 0;
+
+            let len = syscall_los!(unsafe { sys::recvmsg(io.as_raw_fd(), &mut msg, flags) })?;
+
+            let addr = socket_addr(unsafe { addr.assume_init_ref() }, msg.msg_namelen)?;
+
+            Ok((len as usize, addr, msg.msg_flags & sys::MSG_TRUNC != 0))
+        })
+        .await
+    }
+
+    /// Receives a single datagram message, storing the raw address of the sender in a caller-provided buffer.
+    ///
+    /// Returns the number of bytes read and the length of the address stored in `addr`.
+    ///
+    /// This is a variant of [`recv_from()`][`Async::<UdpSocket>::recv_from()`] for hot receive paths,
+    /// where constructing a [`SocketAddr`] for each message is unnecessary. The address can be parsed
+    /// lazily by the caller, if and when needed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::mem::MaybeUninit;
+    ///
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let mut addr = MaybeUninit::<libc::sockaddr_storage>::uninit();
+    /// let (len, addr_len) = socket.recv_from_into(&mut buf, &mut addr).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_from_into(
+        &self,
+        buf: &mut [u8],
+        addr: &mut core::mem::MaybeUninit<sys::sockaddr_storage>,
+    ) -> io::Result<(usize, sys::socklen_t)> {
+        self.read_with(|io| {
+            let mut addr_len = core::mem::size_of::<sys::sockaddr_storage>() as sys::socklen_t;
+
+            let len = syscall_los!(unsafe {
+                sys::recvfrom(
+                    io.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut _,
+                    buf.len(),
+                    0,
+                    addr.as_mut_ptr() as *mut _,
+                    &mut addr_len,
+                )
+            })?;
+
+            Ok((len as usize, addr_len))
+        })
+        .await
+    }
+
+    /// Receives a batch of datagram messages, waiting only until the first one is available.
+    ///
+    /// Datagram `i` is stored in `bufs[i]`, and its length and sender address in `meta[i]`, for up to
+    /// `min(bufs.len(), meta.len())` datagrams. Returns the number of datagrams received, which is 0 only
+    /// if `bufs` or `meta` is empty. As with [`recv_from()`][`Async::<UdpSocket>::recv_from()`], the excess
+    /// bytes of a datagram longer than its buffer are discarded.
+    ///
+    /// On Linux and Android, the datagrams already queued on the socket are received with a single `recvmmsg` call
+    /// (per 16 datagrams), which greatly reduces the syscall and reactor overhead of bursty traffic. Other platforms
+    /// (e.g. the ESP-IDF) fall back to a `recvfrom` call per datagram, but still need only one reactor round-trip
+    /// for the whole batch.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::{SocketAddr, UdpSocket};
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut storage = [[0u8; 1500]; 8];
+    /// let mut bufs = storage.each_mut().map(|buf| buf.as_mut_slice());
+    /// let mut meta = [(0, SocketAddr::from(([0, 0, 0, 0], 0))); 8];
+    ///
+    /// let count = socket.recv_batch(&mut bufs, &mut meta).await?;
+    ///
+    /// for (buf, (len, addr)) in bufs.iter().zip(&meta).take(count) {
+    ///     println!("Received {:?} from {addr}", &buf[..*len]);
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_batch(
+        &self,
+        bufs: &mut [&mut [u8]],
+        meta: &mut [(usize, SocketAddr)],
+    ) -> io::Result<usize> {
+        let len = bufs.len().min(meta.len());
+
+        if len == 0 {
+            return Ok(0);
+        }
+
+        self.read_with(|io| {
+            let mut received = 0;
+
+            while received < len {
+                let chunk = match recv_chunk(io, &mut bufs[received..len], &mut meta[received..len])
+                {
+                    Ok(chunk) => chunk,
+                    // Only fail with `WouldBlock` (i.e. wait for readiness) if nothing was received yet
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock && received > 0 => break,
+                    Err(err) => Err(err)?,
+                };
+
+                received += chunk.received;
+
+                if chunk.drained {
+                    break;
+                }
+            }
+
+            Ok(received)
+        })
+        .await
+    }
+
+    /// Receives a single datagram message directly into the free region of the ring buffer.
+    ///
+    /// Returns the number of bytes received, which are already committed to the ring buffer.
+    ///
+    /// If the free region wraps around, the datagram is scattered into both of its segments with a single
+    /// `recvmsg` call, so there is no intermediate copy.
+    ///
+    /// If the ring buffer is full, fails immediately with an error of kind [`io::ErrorKind::OutOfMemory`] and
+    /// leaves the datagram in the queue, so that a zero-length datagram is not mistaken for a full ring buffer.
+    /// If the datagram is longer than the free region, it is discarded without committing any of it, and an error
+    /// of kind [`io::ErrorKind::InvalidData`] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::{Async, RingBuffer};
+    /// use std::net::UdpSocket;
+    ///
+    /// struct Ring {
+    ///     buf: [u8; 4096],
+    ///     head: usize,
+    ///     len: usize,
+    /// }
+    ///
+    /// impl RingBuffer for Ring {
+    ///     fn free_segments(&mut self) -> (&mut [u8], &mut [u8]) {
+    ///         let tail = (self.head + self.len) % self.buf.len();
+    ///         let free = self.buf.len() - self.len;
+    ///
+    ///         let (start, end) = self.buf.split_at_mut(tail);
+    ///         let first = free.min(end.len());
+    ///
+    ///         (&mut end[..first], &mut start[..free - first])
+    ///     }
+    ///
+    ///     fn commit(&mut self, len: usize) {
+    ///         self.len += len;
+    ///     }
+    /// }
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut ring = Ring { buf: [0; 4096], head: 0, len: 0 };
+    /// let len = socket.recv_into_ring(&mut ring).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv_into_ring<R: RingBuffer + ?Sized>(&self, ring: &mut R) -> io::Result<usize> {
+        let len = self
+            .read_with(|io| {
+                let (first, second) = ring.free_segments();
+
+                if first.is_empty() && second.is_empty() {
+                    Err(io::Error::new(
+                        io::ErrorKind::OutOfMemory,
+                        "the ring buffer is full",
+                    ))?;
+                }
+
+                let mut iov = [
+                    sys::iovec {
+                        iov_base: first.as_mut_ptr() as *mut _,
+                        iov_len: first.len(),
+                    },
+                    sys::iovec {
+                        iov_base: second.as_mut_ptr() as *mut _,
+                        iov_len: second.len(),
+                    },
+                ];
+
+                let mut msg: sys::msghdr = unsafe { core::mem::zeroed() };
+                msg.msg_iov = iov.as_mut_ptr();
+                msg.msg_iovlen = if second.is_empty() { 1 } else { 2 } as _;
+
+                let len = syscall_los!(unsafe { sys::recvmsg(io.as_raw_fd(), &mut msg, 0) })?;
+
+                if msg.msg_flags & sys::MSG_TRUNC != 0 {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "the datagram does not fit into the ring buffer and was discarded",
+                    ))?;
+                }
+
+                Ok(len as usize)
+            })
+            .await?;
+
+        ring.commit(len);
+
+        Ok(len)
+    }
+
+    /// Receives a single datagram message without removing it from the queue.
+    ///
+    /// Returns the number of bytes read and the address the message came from.
+    ///
+    /// This method must be called with a valid byte slice of sufficient size to hold the message.
+    /// If the message is too long to fit, excess bytes may get discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let (len, addr) = socket.peek_from(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.read_with(|io| io.peek_from(buf)).await
+    }
+
+    /// Sends data to the specified address.
+    ///
+    /// Returns the number of bytes writen.
     ///
     /// # Examples
     ///
@@ -1081,20 +2783,379 @@ impl Async<UdpSocket> {
     /// let len = socket.send_to(msg, addr).await?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn send_to<A: Into<SocketAddr>>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
-        let addr = addr.into();
-        self.write_with(|io| io.send_to(buf, addr)).await
+    pub async fn send_to<A: Into<SocketAddr>>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
+        let addr = addr.into();
+        self.write_with(|io| io.send_to(buf, addr)).await
+    }
+
+    /// Receives a single datagram message from the connected peer.
+    ///
+    /// Returns the number of bytes read.
+    ///
+    /// This method must be called with a valid byte slice of sufficient size to hold the message.
+    /// If the message is too long to fit, excess bytes may get discarded.
+    ///
+    /// The [`connect`][`Async::<UdpSocket>::connect()`] method connects this socket to a remote address.
+    /// This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    /// socket.connect(([127, 0, 0, 1], 9000))?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let len = socket.recv(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|io| io.recv(buf)).await
+    }
+
+    /// Receives a single datagram message from the connected peer without removing it from the
+    /// queue.
+    ///
+    /// Returns the number of bytes read and the address the message came from.
+    ///
+    /// This method must be called with a valid byte slice of sufficient size to hold the message.
+    /// If the message is too long to fit, excess bytes may get discarded.
+    ///
+    /// The [`connect`][`Async::<UdpSocket>::connect()`] method connects this socket to a remote address.
+    /// This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    /// socket.connect(([127, 0, 0, 1], 9000))?;
+    ///
+    /// let mut buf = [0u8; 1024];
+    /// let len = socket.peek(&mut buf).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|io| io.peek(buf)).await
+    }
+
+    /// Sends data to the connected peer.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// The [`connect`][`Async::<UdpSocket>::connect()`] method connects this socket to a remote address.
+    /// This method will fail if the socket is not connected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+    /// socket.connect(([127, 0, 0, 1], 9000))?;
+    ///
+    /// let msg = b"hello";
+    /// let len = socket.send(msg).await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(|io| io.send(buf)).await
+    }
+
+    /// Takes the pending error of the socket (`SO_ERROR`), if any, clearing it.
+    ///
+    /// On a connected socket, an ICMP "port unreachable" message received in response to a previously sent datagram
+    /// sets the pending error to `ECONNREFUSED`, which then fails the next [`send()`][`Async::<UdpSocket>::send()`] or
+    /// [`recv()`][`Async::<UdpSocket>::recv()`] call (and clears it). Such errors are transient: the socket remains
+    /// usable, and subsequent calls succeed, unless another ICMP message arrives.
+    ///
+    /// Calling this method before sending allows distinguishing such a transient, asynchronous error
+    /// (caused by a previous datagram) from an error caused by the datagram being sent.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.connect(([127, 0, 0, 1], 9000))?;
+    ///
+    /// if let Some(err) = socket.take_error()? {
+    ///     println!("The peer did not receive a previous datagram: {err}");
+    /// }
+    ///
+    /// socket.send(b"hello").await?;
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.get_ref().take_error()
+    }
+
+    /// Enables or disables the extended reliable error reporting (`IP_RECVERR`, or `IPV6_RECVERR` for IPv6 sockets).
+    ///
+    /// When enabled, the errors of the sent datagrams (e.g. ICMP "port unreachable" or "fragmentation needed" messages)
+    /// are queued in the error queue of the socket, with the details of which destination failed and why.
+    /// Use [`recv_error()`][`Async::<UdpSocket>::recv_error()`] to read them.
+    ///
+    /// Only available on Linux and Android; the ESP-IDF (lwIP) has no error queue.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_recverr(&self, on: bool) -> io::Result<()> {
+        let (level, name) = match self.get_ref().local_addr()? {
+            SocketAddr::V4(_) => (sys::IPPROTO_IP, sys::IP_RECVERR),
+            SocketAddr::V6(_) => (sys::IPPROTO_IPV6, sys::IPV6_RECVERR),
+        };
+
+        setsockopt(self.as_fd(), level, name, on as sys::c_int)
+    }
+
+    /// Reads the next error from the error queue of the socket (`MSG_ERRQUEUE`), if any.
+    ///
+    /// Returns `None` if the error queue is empty. A queued error makes the socket readable, so this method is best
+    /// called when a read readiness wait completes, or when a send or receive fails with an error.
+    ///
+    /// Requires the extended reliable error reporting to be enabled with
+    /// [`set_recverr()`][`Async::<UdpSocket>::set_recverr()`].
+    ///
+    /// Only available on Linux and Android; the ESP-IDF (lwIP) has no error queue.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    /// socket.set_recverr(true)?;
+    ///
+    /// socket.send_to(b"hello", ([127, 0, 0, 1], 9000)).await?;
+    ///
+    /// while let Some(err) = socket.recv_error()? {
+    ///     println!("Sending to {:?} failed: {}", err.destination, err.error());
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn recv_error(&self) -> io::Result<Option<SockExtendedErr>> {
+        let mut destination = core::mem::MaybeUninit::<sys::sockaddr_storage>::zeroed();
+        // The payload (i.e. the original datagram) is not needed
+        let mut iov = sys::iovec {
+            iov_base: core::ptr::null_mut(),
+            iov_len: 0,
+        };
+        // `u64` for the alignment of `cmsghdr`
+        let mut control = [0u64; 64];
+
+        let mut msg: sys::msghdr = unsafe { core::mem::zeroed() };
+        msg.msg_name = destination.as_mut_ptr() as *mut _;
+        msg.msg_namelen = core::mem::size_of::<sys::sockaddr_storage>() as _;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut _;
+        msg.msg_controllen = core::mem::size_of_val(&control) as _;
+
+        match syscall_los!(unsafe {
+            sys::recvmsg(
+                self.as_fd().as_raw_fd(),
+                &mut msg,
+                sys::MSG_ERRQUEUE | sys::MSG_DONTWAIT,
+            )
+        }) {
+            Ok(_) => (),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            Err(err) => Err(err)?,
+        }
+
+        let destination =
+            socket_addr(unsafe { destination.assume_init_ref() }, msg.msg_namelen).ok();
+
+        let mut cmsg = unsafe { sys::CMSG_FIRSTHDR(&msg) };
+
+        while let Some(header) = unsafe { cmsg.as_ref() } {
+            if (header.cmsg_level == sys::IPPROTO_IP && header.cmsg_type == sys::IP_RECVERR)
+                || (header.cmsg_level == sys::IPPROTO_IPV6 && header.cmsg_type == sys::IPV6_RECVERR)
+            {
+                let data = unsafe { sys::CMSG_DATA(cmsg) };
+                let err = unsafe { (data as *const sys::sock_extended_err).read_unaligned() };
+
+                // The address of the node which originated the error follows the error (`SO_EE_OFFENDER`)
+                let offset = unsafe { data.offset_from(cmsg as *const u8) } as usize
+                    + core::mem::size_of::<sys::sock_extended_err>();
+                // `cmsg_len` is not a `usize` on all libcs (e.g. musl)
+                #[allow(clippy::unnecessary_cast)]
+                let len = (header.cmsg_len as usize)
+                    .saturating_sub(offset)
+                    .min(core::mem::size_of::<sys::sockaddr_storage>());
+
+                let mut offender = core::mem::MaybeUninit::<sys::sockaddr_storage>::zeroed();
+
+                unsafe {
+                    core::ptr::copy_nonoverlapping(
+                        data.add(core::mem::size_of::<sys::sock_extended_err>()),
+                        offender.as_mut_ptr() as *mut u8,
+                        len,
+                    );
+                }
+
+                let offender = socket_addr(unsafe { offender.assume_init_ref() }, len as _).ok();
+
+                return Ok(Some(SockExtendedErr {
+                    errno: err.ee_errno as _,
+                    origin: err.ee_origin,
+                    icmp_type: err.ee_type,
+                    icmp_code: err.ee_code,
+                    info: err.ee_info,
+                    offender,
+                    destination,
+                }));
+            }
+
+            cmsg = unsafe { sys::CMSG_NXTHDR(&msg, cmsg) };
+        }
+
+        Ok(None)
+    }
+
+    /// Enables or disables the kernel receive timestamps (`SO_TIMESTAMPNS`) for this socket.
+    ///
+    /// When enabled, the kernel timestamps each datagram as it is received, with nanosecond resolution. The timestamp
+    /// is read with [`recv_with_timestamp()`][`Async::<UdpSocket>::recv_with_timestamp()`] and - unlike a timestamp
+    /// taken by the application - is free of the jitter of the scheduling of the receiving task, which matters
+    /// e.g. for clock synchronization protocols.
+    ///
+    /// Only available on Linux and Android. The ESP-IDF (lwIP) does not timestamp the received datagrams.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_timestamping(&self, on: bool) -> io::Result<()> {
+        setsockopt(
+            self.as_fd(),
+            sys::SOL_SOCKET,
+            sys::SO_TIMESTAMPNS,
+            on as sys::c_int,
+        )
+    }
+
+    /// Receives a single datagram message, together with its kernel receive timestamp.
+    ///
+    /// Returns the number of bytes read, the address the message came from, and the time the kernel received
+    /// the message at (as per the system realtime clock). Requires the timestamping to be enabled with
+    /// [`set_timestamping()`][`Async::<UdpSocket>::set_timestamping()`]; otherwise - or if the kernel did not
+    /// timestamp the message, or if the ancillary data of the message was truncated (e.g. as other ancillary data
+    /// is enabled on the socket as well) - the time is `None`.
+    ///
+    /// As with [`recv_from()`][`Async::<UdpSocket>::recv_from()`], the excess bytes of a message which does not fit
+    /// into `buf` are discarded.
+    ///
+    /// Only available on Linux and Android. The ESP-IDF (lwIP) does not timestamp the received datagrams.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use async_io_mini::Async;
+    /// use std::net::UdpSocket;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 123))?;
+    /// socket.set_timestamping(true)?;
+    ///
+    /// let mut buf = [0u8; 48];
+    /// let (len, addr, received_at) = socket.recv_with_timestamp(&mut buf).await?;
+    ///
+    /// if let Some(received_at) = received_at {
+    ///     println!("Received {len} bytes from {addr} at {received_at:?}");
+    /// }
+    /// # std::io::Result::Ok(()) });
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub async fn recv_with_timestamp(
+        &self,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr, Option<std::time::SystemTime>)> {
+        self.read_with(|io| {
+            let mut addr = core::mem::MaybeUninit::<sys::sockaddr_storage>::zeroed();
+
+            let mut iov = sys::iovec {
+                iov_base: buf.as_mut_ptr() as *mut _,
+                iov_len: buf.len(),
+            };
+            // `u64` for the alignment of `cmsghdr`
+            let mut control = [0u64; 8];
+
+            let mut msg: sys::msghdr = unsafe { core::mem::zeroed() };
+            msg.msg_name = addr.as_mut_ptr() as *mut _;
+            msg.msg_namelen = core::mem::size_of::<sys::sockaddr_storage>() as _;
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut _;
+            msg.msg_controllen = core::mem::size_of_val(&control) as _;
+
+            let len = syscall_los!(unsafe { sys::recvmsg(io.as_raw_fd(), &mut msg, 0) })? as usize;
+
+            let addr = socket_addr(unsafe { addr.assume_init_ref() }, msg.msg_namelen)?;
+
+            let mut timestamp = None;
+
+            // A truncated control buffer cannot be trusted to hold the timestamp
+            let mut cmsg = if msg.msg_flags & sys::MSG_CTRUNC == 0 {
+                unsafe { sys::CMSG_FIRSTHDR(&msg) }
+            } else {
+                core::ptr::null_mut()
+            };
+
+            while let Some(header) = unsafe { cmsg.as_ref() } {
+                if header.cmsg_level == sys::SOL_SOCKET && header.cmsg_type == sys::SCM_TIMESTAMPNS
+                {
+                    let data = unsafe { sys::CMSG_DATA(cmsg) };
+                    let ts = unsafe { (data as *const sys::timespec).read_unaligned() };
+
+                    timestamp = Some(
+                        std::time::UNIX_EPOCH
+                            + core::time::Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32),
+                    );
+                    break;
+                }
+
+                cmsg = unsafe { sys::CMSG_NXTHDR(&msg, cmsg) };
+            }
+
+            Ok((len, addr, timestamp))
+        })
+        .await
     }
 
-    /// Receives a single datagram message from the connected peer.
+    /// Enables or disables the generic receive offload (`UDP_GRO`) for this socket.
     ///
-    /// Returns the number of bytes read.
+    /// When enabled, the kernel may coalesce several consecutive datagrams of the same flow and size into
+    /// a single buffer, which is then received with a single [`recv_gro()`][`Async::<UdpSocket>::recv_gro()`]
+    /// call, thus saving syscalls and reactor round-trips for bulk UDP traffic.
     ///
-    /// This method must be called with a valid byte slice of sufficient size to hold the message.
-    /// If the message is too long to fit, excess bytes may get discarded.
+    /// Requires Linux 5.0 or later. Only available on Linux and Android.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_gro(&self, on: bool) -> io::Result<()> {
+        setsockopt(self.as_fd(), sys::SOL_UDP, UDP_GRO, on as sys::c_int)
+    }
+
+    /// Receives one or more coalesced datagrams from the socket, when the generic receive offload is enabled
+    /// with [`set_gro()`][`Async::<UdpSocket>::set_gro()`].
     ///
-    /// The [`connect`][`UdpSocket::connect()`] method connects this socket to a remote address.
-    /// This method will fail if the socket is not connected.
+    /// Returns the total number of bytes read, and the size of the coalesced segments. The buffer holds
+    /// consecutive datagrams of the segment size, except for the last one, which may be shorter. If the kernel
+    /// did not coalesce anything, the buffer holds a single datagram, and the segment size is its length.
+    ///
+    /// The buffer should be large enough for a coalesced read (i.e. 64KB); otherwise the excess bytes
+    /// are discarded, as with [`recv_from()`][`Async::<UdpSocket>::recv_from()`].
+    ///
+    /// Only available on Linux and Android. Elsewhere, use [`recv()`][`Async::<UdpSocket>::recv()`] or
+    /// [`recv_from()`][`Async::<UdpSocket>::recv_from()`], which always return a single datagram.
     ///
     /// # Examples
     ///
@@ -1104,26 +3165,64 @@ impl Async<UdpSocket> {
     ///
     /// # futures_lite::future::block_on(async {
     /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
-    /// socket.get_ref().connect("127.0.0.1:9000")?;
+    /// socket.set_gro(true)?;
     ///
-    /// let mut buf = [0u8; 1024];
-    /// let len = socket.recv(&mut buf).await?;
+    /// let mut buf = vec![0u8; 65536];
+    /// let (len, segment_size) = socket.recv_gro(&mut buf).await?;
+    ///
+    /// for datagram in buf[..len].chunks(segment_size as usize) {
+    ///     println!("Received {} bytes", datagram.len());
+    /// }
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.read_with(|io| io.recv(buf)).await
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub async fn recv_gro(&self, buf: &mut [u8]) -> io::Result<(usize, u16)> {
+        self.read_with(|io| {
+            let mut iov = sys::iovec {
+                iov_base: buf.as_mut_ptr() as *mut _,
+                iov_len: buf.len(),
+            };
+            // `u64` for the alignment of `cmsghdr`
+            let mut control = [0u64; 8];
+
+            let mut msg: sys::msghdr = unsafe { core::mem::zeroed() };
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut _;
+            msg.msg_controllen = core::mem::size_of_val(&control) as _;
+
+            let len = syscall_los!(unsafe { sys::recvmsg(io.as_raw_fd(), &mut msg, 0) })? as usize;
+
+            let mut segment_size = len.min(u16::MAX as usize) as u16;
+
+            let mut cmsg = unsafe { sys::CMSG_FIRSTHDR(&msg) };
+
+            while let Some(header) = unsafe { cmsg.as_ref() } {
+                if header.cmsg_level == sys::SOL_UDP && header.cmsg_type == UDP_GRO {
+                    let data = unsafe { sys::CMSG_DATA(cmsg) };
+                    let size = unsafe { (data as *const sys::c_int).read_unaligned() };
+
+                    segment_size = size as u16;
+                    break;
+                }
+
+                cmsg = unsafe { sys::CMSG_NXTHDR(&msg, cmsg) };
+            }
+
+            Ok((len, segment_size))
+        })
+        .await
     }
 
-    /// Receives a single datagram message from the connected peer without removing it from the
-    /// queue.
+    /// Enables or disables sending of broadcast datagrams (`SO_BROADCAST`) on this socket.
     ///
-    /// Returns the number of bytes read and the address the message came from.
-    ///
-    /// This method must be called with a valid byte slice of sufficient size to hold the message.
-    /// If the message is too long to fit, excess bytes may get discarded.
+    /// Once enabled, [`send_to()`][`Async::<UdpSocket>::send_to()`] can be used with a broadcast address
+    /// like `255.255.255.255`.
     ///
-    /// The [`connect`][`UdpSocket::connect()`] method connects this socket to a remote address.
-    /// This method will fail if the socket is not connected.
+    /// Note that the limited broadcast address `255.255.255.255` is sent via the interface selected by the routing
+    /// table (i.e. usually the interface of the default route), unless the socket is bound to the address of
+    /// a specific interface or - on Linux - to a specific interface with [`bind_device()`][`Async::<UdpSocket>::bind_device()`].
+    /// Using the directed broadcast address of a subnet (e.g. `192.168.1.255`) selects the interface of that subnet.
     ///
     /// # Examples
     ///
@@ -1132,23 +3231,46 @@ impl Async<UdpSocket> {
     /// use std::net::UdpSocket;
     ///
     /// # futures_lite::future::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
-    /// socket.get_ref().connect("127.0.0.1:9000")?;
+    /// let socket = Async::<UdpSocket>::bind(([0, 0, 0, 0], 0))?;
+    /// socket.set_broadcast(true)?;
     ///
-    /// let mut buf = [0u8; 1024];
-    /// let len = socket.peek(&mut buf).await?;
+    /// socket.send_to(b"hello", ([255, 255, 255, 255], 9000)).await?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.read_with(|io| io.peek(buf)).await
+    pub fn set_broadcast(&self, broadcast: bool) -> io::Result<()> {
+        self.get_ref().set_broadcast(broadcast)
     }
 
-    /// Sends data to the connected peer.
-    ///
-    /// Returns the number of bytes written.
+    /// Returns whether sending of broadcast datagrams (`SO_BROADCAST`) is enabled on this socket.
+    pub fn broadcast(&self) -> io::Result<bool> {
+        self.get_ref().broadcast()
+    }
+
+    /// Binds the socket to a specific network interface (`SO_BINDTODEVICE`), so that datagrams are
+    /// only sent and received via that interface.
+    ///
+    /// Passing an empty interface name removes the binding.
+    ///
+    /// Requires the `CAP_NET_RAW` capability.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn bind_device(&self, ifname: &str) -> io::Result<()> {
+        syscall_los!(unsafe {
+            sys::setsockopt(
+                self.as_fd().as_raw_fd(),
+                sys::SOL_SOCKET,
+                sys::SO_BINDTODEVICE,
+                ifname.as_ptr() as *const _,
+                ifname.len() as _,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Sets the type-of-service (`IP_TOS`) field of the outgoing packets, or the traffic class
+    /// (`IPV6_TCLASS`) for IPv6 sockets.
     ///
-    /// The [`connect`][`UdpSocket::connect()`] method connects this socket to a remote address.
-    /// This method will fail if the socket is not connected.
+    /// This is typically used for DSCP marking, where the DSCP value occupies the upper 6 bits.
     ///
     /// # Examples
     ///
@@ -1157,15 +3279,51 @@ impl Async<UdpSocket> {
     /// use std::net::UdpSocket;
     ///
     /// # futures_lite::future::block_on(async {
-    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
-    /// socket.get_ref().connect("127.0.0.1:9000")?;
+    /// let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
     ///
-    /// let msg = b"hello";
-    /// let len = socket.send(msg).await?;
+    /// // DSCP EF (Expedited Forwarding)
+    /// socket.set_tos(46 << 2)?;
     /// # std::io::Result::Ok(()) });
     /// ```
-    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        self.write_with(|io| io.send(buf)).await
+    pub fn set_tos(&self, tos: u32) -> io::Result<()> {
+        set_tos(self.as_fd(), self.get_ref().local_addr()?, tos)
+    }
+
+    /// Gets the type-of-service (`IP_TOS`) field of the outgoing packets, or the traffic class
+    /// (`IPV6_TCLASS`) for IPv6 sockets.
+    ///
+    /// For more information about this option, see [`set_tos()`][`Async::<UdpSocket>::set_tos()`].
+    pub fn tos(&self) -> io::Result<u32> {
+        tos(self.as_fd(), self.get_ref().local_addr()?)
+    }
+
+    /// Sets the size of the socket send buffer (`SO_SNDBUF`), in bytes.
+    ///
+    /// Larger buffers help to keep links with a high bandwidth-delay product (e.g. satellite links) saturated.
+    /// Note that the OS may adjust the requested size (Linux e.g. doubles it for bookkeeping overhead and caps it
+    /// at `net.core.wmem_max`), so use [`send_buffer_size()`][`Async::<UdpSocket>::send_buffer_size()`] to get
+    /// the effective size.
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        set_buffer_size(self.as_fd(), sys::SO_SNDBUF, size)
+    }
+
+    /// Gets the effective size of the socket send buffer (`SO_SNDBUF`), in bytes.
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        buffer_size(self.as_fd(), sys::SO_SNDBUF)
+    }
+
+    /// Sets the size of the socket receive buffer (`SO_RCVBUF`), in bytes.
+    ///
+    /// As with [`set_send_buffer_size()`][`Async::<UdpSocket>::set_send_buffer_size()`], the OS may adjust
+    /// the requested size, so use [`recv_buffer_size()`][`Async::<UdpSocket>::recv_buffer_size()`] to get
+    /// the effective size.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        set_buffer_size(self.as_fd(), sys::SO_RCVBUF, size)
+    }
+
+    /// Gets the effective size of the socket receive buffer (`SO_RCVBUF`), in bytes.
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        buffer_size(self.as_fd(), sys::SO_RCVBUF)
     }
 }
 
@@ -1177,6 +3335,328 @@ impl TryFrom<std::net::UdpSocket> for Async<std::net::UdpSocket> {
     }
 }
 
+/// An extended socket error, as read from the error queue of a socket with
+/// [`Async::<UdpSocket>::recv_error()`].
+///
+/// Only available on Linux and Android.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SockExtendedErr {
+    /// The error number (e.g. `ECONNREFUSED` for an ICMP "port unreachable" message).
+    pub errno: i32,
+    /// The origin of the error (e.g. `SO_EE_ORIGIN_ICMP`).
+    pub origin: u8,
+    /// The ICMP type, for ICMP errors.
+    pub icmp_type: u8,
+    /// The ICMP code, for ICMP errors.
+    pub icmp_code: u8,
+    /// Additional information, e.g. the discovered path MTU for `EMSGSIZE` errors.
+    pub info: u32,
+    /// The address of the node which originated the error, if known.
+    pub offender: Option<SocketAddr>,
+    /// The destination address of the datagram which caused the error, if known.
+    pub destination: Option<SocketAddr>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SockExtendedErr {
+    /// Returns the error number as an [`io::Error`].
+    pub fn error(&self) -> io::Error {
+        io::Error::from_raw_os_error(self.errno)
+    }
+}
+
+/// A description of the readiness of an I/O handle, as returned by [`Async::<TcpStream>::poll_read_ready()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ready {
+    readable: bool,
+    writable: bool,
+    read_closed: bool,
+    write_closed: bool,
+}
+
+impl Ready {
+    /// Returns `true` if the I/O handle is readable.
+    pub const fn is_readable(&self) -> bool {
+        self.readable
+    }
+
+    /// Returns `true` if the I/O handle is writable.
+    pub const fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Returns `true` if the read half is closed, i.e. the peer will not send any more data.
+    pub const fn is_read_closed(&self) -> bool {
+        self.read_closed
+    }
+
+    /// Returns `true` if the write half is closed, i.e. the stream cannot send any more data.
+    pub const fn is_write_closed(&self) -> bool {
+        self.write_closed
+    }
+}
+
+/// A ring buffer which can be filled in place by [`Async::<UdpSocket>::recv_into_ring()`].
+///
+/// This small trait keeps the receive path decoupled from any specific ring buffer implementation.
+pub trait RingBuffer {
+    /// Returns the free region of the ring buffer as (up to) two contiguous segments, in write order.
+    ///
+    /// The second segment is non-empty only if the free region wraps around the end of the storage.
+    fn free_segments(&mut self) -> (&mut [u8], &mut [u8]);
+
+    /// Marks the first `len` bytes of the free region (as returned by [`RingBuffer::free_segments`])
+    /// as filled.
+    fn commit(&mut self, len: usize);
+}
+
+/// A pool of reusable buffers, which [`Async::<TcpStream>::read_pooled()`] reads into.
+///
+/// The pool only hands out buffers, as the reads fill them in place through [`AsMut`]. Returning the buffers
+/// to the pool is up to the implementation, typically by having [`BufferPool::Buf`] do it when dropped.
+pub trait BufferPool {
+    /// The type of the buffers in the pool.
+    type Buf: AsRef<[u8]> + AsMut<[u8]>;
+
+    /// Takes a free buffer from the pool, or returns `None` if the pool is exhausted.
+    fn acquire(&self) -> Option<Self::Buf>;
+}
+
+/// A buffer of a [`BufferPool`], partially filled by [`Async::<TcpStream>::read_pooled()`].
+///
+/// Dereferences to the filled part of the buffer.
+#[derive(Debug)]
+pub struct PooledBuf<B> {
+    buf: B,
+    len: usize,
+}
+
+impl<B: AsRef<[u8]>> PooledBuf<B> {
+    /// Returns the number of filled bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no bytes were filled, i.e. the read has reached EOF.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Unwraps the underlying buffer of the pool, regardless of how much of it is filled.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+impl<B: AsRef<[u8]>> core::ops::Deref for PooledBuf<B> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf.as_ref()[..self.len]
+    }
+}
+
+/// Waits until any of the provided I/O handles is ready for any of the events it is paired with.
+///
+/// Resolves with the index of the first ready I/O handle in `sources`, together with the events it is ready for.
+///
+/// This is a cheap, allocation-free way to wait on a small set of I/O handles, which checks and arms all of them
+/// in a single reactor round-trip. Note that the wakers of the I/O handles which did not become ready stay
+/// armed until the next reactor pass observes them being ready, so the task might be woken up spuriously later.
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_io_mini::{ready_any, Async, Event};
+/// use std::net::UdpSocket;
+///
+/// # futures_lite::future::block_on(async {
+/// let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8000))?;
+/// let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 8001))?;
+///
+/// let (index, events) = ready_any(&[
+///     (&socket1, Event::Read.into()),
+///     (&socket2, Event::Read.into()),
+/// ])
+/// .await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+pub async fn ready_any<T: AsFd>(
+    sources: &[(&Async<T>, EnumSet<Event>)],
+) -> io::Result<(usize, EnumSet<Event>)> {
+    poll_fn(|cx| {
+        let ready = REACTOR.fetch_or_set_any(
+            sources
+                .iter()
+                .map(|(source, events)| (source.as_fd().as_raw_fd(), *events)),
+            cx.waker(),
+        )?;
+
+        match ready {
+            Some(ready) => Poll::Ready(Ok(ready)),
+            None => Poll::Pending,
+        }
+    })
+    .await
+}
+
+/// Copies data in both directions between two TCP streams, until both directions reach EOF.
+///
+/// Returns the number of bytes copied from `a` to `b` and from `b` to `a`.
+///
+/// When one of the streams reaches EOF, the write half of the other stream is shut down, and the copying
+/// in the remaining direction continues until it reaches EOF as well.
+///
+/// On Linux, the data is moved with `splice` via a pipe, so it never enters userspace. Elsewhere
+/// (e.g. on the ESP-IDF), the data is copied through a small buffer.
+///
+/// # Examples
+///
+/// ```no_run
+/// use async_io_mini::{copy_bidirectional, Async};
+/// use std::net::{TcpListener, TcpStream};
+///
+/// # futures_lite::future::block_on(async {
+/// let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 8000))?;
+///
+/// let (client, _) = listener.accept().await?;
+/// let upstream = Async::<TcpStream>::connect(([127, 0, 0, 1], 9000)).await?;
+///
+/// let (sent, received) = copy_bidirectional(&client, &upstream).await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+pub async fn copy_bidirectional(
+    a: &Async<TcpStream>,
+    b: &Async<TcpStream>,
+) -> io::Result<(u64, u64)> {
+    let mut a_to_b = pin!(copy_one_way(a, b));
+    let mut b_to_a = pin!(copy_one_way(b, a));
+
+    let mut a_to_b_len = None;
+    let mut b_to_a_len = None;
+
+    poll_fn(move |cx| {
+        if a_to_b_len.is_none() {
+            if let Poll::Ready(len) = a_to_b.as_mut().poll(cx) {
+                a_to_b_len = Some(len?);
+            }
+        }
+
+        if b_to_a_len.is_none() {
+            if let Poll::Ready(len) = b_to_a.as_mut().poll(cx) {
+                b_to_a_len = Some(len?);
+            }
+        }
+
+        match (a_to_b_len, b_to_a_len) {
+            (Some(a_to_b_len), Some(b_to_a_len)) => Poll::Ready(Ok((a_to_b_len, b_to_a_len))),
+            _ => Poll::Pending,
+        }
+    })
+    .await
+}
+
+/// Copies data from one TCP stream to another, until EOF, and then shuts down the write half of the destination.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+async fn copy_one_way(from: &Async<TcpStream>, to: &Async<TcpStream>) -> io::Result<u64> {
+    const CHUNK: usize = 65536;
+
+    let mut pipe = [0; 2];
+    syscall_los!(unsafe { sys::pipe2(pipe.as_mut_ptr(), sys::O_NONBLOCK | sys::O_CLOEXEC) })?;
+
+    let (pipe_r, pipe_w) =
+        unsafe { (OwnedFd::from_raw_fd(pipe[0]), OwnedFd::from_raw_fd(pipe[1])) };
+
+    let splice = |from: RawFd, to: RawFd, len: usize| {
+        syscall_los!(unsafe {
+            sys::splice(
+                from,
+                core::ptr::null_mut(),
+                to,
+                core::ptr::null_mut(),
+                len,
+                sys::SPLICE_F_MOVE | sys::SPLICE_F_NONBLOCK,
+            )
+        })
+        .map(|len| len as usize)
+    };
+
+    let mut total = 0;
+
+    loop {
+        let len = from
+            .read_with(|io| splice(io.as_raw_fd(), pipe_w.as_raw_fd(), CHUNK))
+            .await?;
+
+        if len == 0 {
+            break;
+        }
+
+        let mut pending = len;
+
+        while pending > 0 {
+            let written = to
+                .write_with(|io| splice(pipe_r.as_raw_fd(), io.as_raw_fd(), pending))
+                .await?;
+
+            if written == 0 {
+                Err(io::ErrorKind::WriteZero)?;
+            }
+
+            pending -= written;
+        }
+
+        total += len as u64;
+    }
+
+    shutdown_write(to)?;
+
+    Ok(total)
+}
+
+/// Copies data from one TCP stream to another, until EOF, and then shuts down the write half of the destination.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+async fn copy_one_way(from: &Async<TcpStream>, to: &Async<TcpStream>) -> io::Result<u64> {
+    let mut buf = [0; 512];
+    let mut total = 0;
+
+    loop {
+        let len = from.read_with(|mut io| io.read(&mut buf)).await?;
+
+        if len == 0 {
+            break;
+        }
+
+        let mut offset = 0;
+
+        while offset < len {
+            let written = to.write_with(|mut io| io.write(&buf[offset..len])).await?;
+
+            if written == 0 {
+                Err(io::ErrorKind::WriteZero)?;
+            }
+
+            offset += written;
+        }
+
+        total += len as u64;
+    }
+
+    shutdown_write(to)?;
+
+    Ok(total)
+}
+
+fn shutdown_write(stream: &Async<TcpStream>) -> io::Result<()> {
+    match stream.get_ref().shutdown(std::net::Shutdown::Write) {
+        // The peer is already gone, so there is nothing to shut down
+        Err(err) if err.kind() == io::ErrorKind::NotConnected => Ok(()),
+        other => other,
+    }
+}
+
 /// Polls a future once, waits for a wakeup, and then optimistically assumes the future is ready.
 async fn optimistic(fut: impl Future<Output = io::Result<()>>) -> io::Result<()> {
     let mut polled = false;
@@ -1193,6 +3673,28 @@ async fn optimistic(fut: impl Future<Output = io::Result<()>>) -> io::Result<()>
     .await
 }
 
+/// Runs a future until it completes or until the deadline passes, in which case an error of kind
+/// [`io::ErrorKind::TimedOut`] is returned.
+#[cfg(feature = "embassy-time")]
+pub(crate) async fn timeout_at<R>(
+    fut: impl Future<Output = io::Result<R>>,
+    deadline: std::time::Instant,
+) -> io::Result<R> {
+    let mut fut = pin!(fut);
+    let mut timer = crate::Timer::at(deadline);
+
+    poll_fn(move |cx| {
+        if let Poll::Ready(result) = fut.as_mut().poll(cx) {
+            Poll::Ready(result)
+        } else if core::pin::Pin::new(&mut timer).poll(cx).is_ready() {
+            Poll::Ready(Err(io::ErrorKind::TimedOut.into()))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
 fn connect(
     addr: *const sys::sockaddr,
     addr_len: usize,
@@ -1211,6 +3713,212 @@ fn connect(
     Ok(socket)
 }
 
+/// Takes the pending error of the socket (`SO_ERROR`), if any, and fails with it.
+fn pending_error(fd: BorrowedFd) -> io::Result<()> {
+    match getsockopt::<sys::c_int>(fd, sys::SOL_SOCKET, sys::SO_ERROR) {
+        Ok(0) => Ok(()),
+        Ok(errno) => Err(io::Error::from_raw_os_error(errno)),
+        // Not a socket, so there is no pending error
+        Err(err) if err.raw_os_error() == Some(sys::ENOTSOCK) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Interprets the error of a connection as "connection is dead", or propagates it, if it is not related
+/// to the state of the connection.
+fn dead_or_err(err: io::Error) -> io::Result<bool> {
+    match err.kind() {
+        io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::BrokenPipe
+        | io::ErrorKind::NotConnected
+        | io::ErrorKind::TimedOut => Ok(false),
+        _ => Err(err),
+    }
+}
+
+/// Accepts a new connection, which is put in non-blocking mode (and marked close-on-exec).
+///
+/// On Linux, this is done atomically and with a single syscall, using `accept4`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn accept(listener: &TcpListener) -> io::Result<(TcpStream, SocketAddr)> {
+    let mut addr = core::mem::MaybeUninit::<sys::sockaddr_storage>::uninit();
+    let mut addr_len = core::mem::size_of::<sys::sockaddr_storage>() as sys::socklen_t;
+
+    let fd = syscall_los!(unsafe {
+        sys::accept4(
+            listener.as_raw_fd(),
+            addr.as_mut_ptr() as *mut _,
+            &mut addr_len,
+            sys::SOCK_NONBLOCK | sys::SOCK_CLOEXEC,
+        )
+    })?;
+
+    let stream = TcpStream::from(unsafe { OwnedFd::from_raw_fd(fd) });
+    let addr = socket_addr(unsafe { addr.assume_init_ref() }, addr_len)?;
+
+    Ok((stream, addr))
+}
+
+/// Accepts a new connection, which is put in non-blocking mode (and marked close-on-exec).
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn accept(listener: &TcpListener) -> io::Result<(TcpStream, SocketAddr)> {
+    let (stream, addr) = listener.accept()?;
+
+    set_nonblocking(stream.as_fd())?;
+
+    Ok((stream, addr))
+}
+
+/// The outcome of a single [`recv_chunk`] call.
+#[cfg_attr(not(any(target_os = "linux", target_os = "android")), allow(unused))]
+struct RecvChunk {
+    /// The number of datagrams received
+    received: usize,
+    /// `true` if fewer datagrams than requested were received, i.e. the socket has no more queued datagrams
+    drained: bool,
+}
+
+/// Receives up to `bufs.len()` datagrams with a single `recvmmsg` call.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn recv_chunk(
+    socket: &UdpSocket,
+    bufs: &mut [&mut [u8]],
+    meta: &mut [(usize, SocketAddr)],
+) -> io::Result<RecvChunk> {
+    const BATCH: usize = 16;
+
+    let len = bufs.len().min(BATCH);
+
+    let mut addrs: [sys::sockaddr_storage; BATCH] = unsafe { core::mem::zeroed() };
+    let mut iovs: [sys::iovec; BATCH] = unsafe { core::mem::zeroed() };
+    let mut msgs: [sys::mmsghdr; BATCH] = unsafe { core::mem::zeroed() };
+
+    for (index, buf) in bufs.iter_mut().take(len).enumerate() {
+        iovs[index] = sys::iovec {
+            iov_base: buf.as_mut_ptr() as *mut _,
+            iov_len: buf.len(),
+        };
+
+        let hdr = &mut msgs[index].msg_hdr;
+        hdr.msg_name = &mut addrs[index] as *mut _ as *mut _;
+        hdr.msg_namelen = core::mem::size_of::<sys::sockaddr_storage>() as _;
+        hdr.msg_iov = &mut iovs[index];
+        hdr.msg_iovlen = 1;
+    }
+
+    let received = syscall_los!(unsafe {
+        sys::recvmmsg(
+            socket.as_raw_fd(),
+            msgs.as_mut_ptr(),
+            len as _,
+            0,
+            core::ptr::null_mut(),
+        )
+    })? as usize;
+
+    for index in 0..received {
+        let msg = &msgs[index];
+
+        meta[index] = (
+            (msg.msg_len as usize).min(bufs[index].len()),
+            socket_addr(&addrs[index], msg.msg_hdr.msg_namelen)?,
+        );
+    }
+
+    Ok(RecvChunk {
+        received,
+        drained: received < len,
+    })
+}
+
+/// Receives a single datagram, as there is no `recvmmsg` outside of Linux.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn recv_chunk(
+    socket: &UdpSocket,
+    bufs: &mut [&mut [u8]],
+    meta: &mut [(usize, SocketAddr)],
+) -> io::Result<RecvChunk> {
+    meta[0] = socket.recv_from(bufs[0])?;
+
+    Ok(RecvChunk {
+        received: 1,
+        drained: false,
+    })
+}
+
+/// Converts a raw socket address into a [`SocketAddr`].
+fn socket_addr(addr: &sys::sockaddr_storage, len: sys::socklen_t) -> io::Result<SocketAddr> {
+    match addr.ss_family as sys::c_int {
+        sys::AF_INET if len as usize >= core::mem::size_of::<sys::sockaddr_in>() => {
+            let addr = unsafe { &*(addr as *const _ as *const sys::sockaddr_in) };
+
+            Ok(SocketAddr::V4(std::net::SocketAddrV4::new(
+                addr.sin_addr.s_addr.to_ne_bytes().into(),
+                u16::from_be(addr.sin_port),
+            )))
+        }
+        sys::AF_INET6 if len as usize >= core::mem::size_of::<sys::sockaddr_in6>() => {
+            let addr = unsafe { &*(addr as *const _ as *const sys::sockaddr_in6) };
+
+            Ok(SocketAddr::V6(std::net::SocketAddrV6::new(
+                addr.sin6_addr.s6_addr.into(),
+                u16::from_be(addr.sin6_port),
+                addr.sin6_flowinfo,
+                addr.sin6_scope_id,
+            )))
+        }
+        _ => Err(io::ErrorKind::InvalidInput.into()),
+    }
+}
+
+/// Converts a socket address into its raw representation.
+fn sockaddr(addr: SocketAddr) -> (sys::sockaddr_storage, sys::socklen_t) {
+    let mut storage = unsafe { core::mem::zeroed::<sys::sockaddr_storage>() };
+
+    let len = match addr {
+        SocketAddr::V4(v4) => {
+            let addr = sys::sockaddr_in {
+                sin_family: sys::AF_INET as _,
+                sin_port: u16::to_be(v4.port()),
+                sin_addr: sys::in_addr {
+                    s_addr: u32::from_ne_bytes(v4.ip().octets()),
+                },
+                #[cfg(target_os = "espidf")]
+                sin_len: Default::default(),
+                sin_zero: Default::default(),
+            };
+
+            unsafe {
+                core::ptr::write(&mut storage as *mut _ as *mut sys::sockaddr_in, addr);
+            }
+
+            core::mem::size_of::<sys::sockaddr_in>()
+        }
+        SocketAddr::V6(v6) => {
+            let addr = sys::sockaddr_in6 {
+                sin6_family: sys::AF_INET6 as _,
+                sin6_port: u16::to_be(v6.port()),
+                sin6_flowinfo: v6.flowinfo(),
+                sin6_addr: sys::in6_addr {
+                    s6_addr: v6.ip().octets(),
+                },
+                sin6_scope_id: v6.scope_id(),
+                #[cfg(target_os = "espidf")]
+                sin6_len: Default::default(),
+            };
+
+            unsafe {
+                core::ptr::write(&mut storage as *mut _ as *mut sys::sockaddr_in6, addr);
+            }
+
+            core::mem::size_of::<sys::sockaddr_in6>()
+        }
+    };
+
+    (storage, len as _)
+}
+
 fn set_nonblocking(fd: BorrowedFd) -> io::Result<()> {
     let previous = unsafe { sys::fcntl(fd.as_raw_fd(), sys::F_GETFL) };
     let new = previous | sys::O_NONBLOCK;
@@ -1220,3 +3928,89 @@ fn set_nonblocking(fd: BorrowedFd) -> io::Result<()> {
 
     Ok(())
 }
+
+fn setsockopt<T>(fd: BorrowedFd, level: sys::c_int, name: sys::c_int, value: T) -> io::Result<()> {
+    syscall_los!(unsafe {
+        sys::setsockopt(
+            fd.as_raw_fd(),
+            level,
+            name,
+            &value as *const _ as *const _,
+            core::mem::size_of::<T>() as _,
+        )
+    })?;
+
+    Ok(())
+}
+
+fn getsockopt<T: Copy>(fd: BorrowedFd, level: sys::c_int, name: sys::c_int) -> io::Result<T> {
+    let mut value = core::mem::MaybeUninit::<T>::zeroed();
+    let mut len = core::mem::size_of::<T>() as sys::socklen_t;
+
+    syscall_los!(unsafe {
+        sys::getsockopt(
+            fd.as_raw_fd(),
+            level,
+            name,
+            value.as_mut_ptr() as *mut _,
+            &mut len,
+        )
+    })?;
+
+    Ok(unsafe { value.assume_init() })
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+fn set_cork(fd: BorrowedFd, cork: bool) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const CORK: sys::c_int = sys::TCP_CORK;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const CORK: sys::c_int = sys::TCP_NOPUSH;
+
+    setsockopt(fd, sys::IPPROTO_TCP, CORK, cork as sys::c_int)
+}
+
+/// Not exposed by `libc` for all Linux libcs (e.g. glibc)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const UDP_GRO: sys::c_int = 104;
+
+fn set_buffer_size(fd: BorrowedFd, name: sys::c_int, size: usize) -> io::Result<()> {
+    let size = sys::c_int::try_from(size).map_err(|_| io::ErrorKind::InvalidInput)?;
+
+    setsockopt(fd, sys::SOL_SOCKET, name, size)
+}
+
+fn buffer_size(fd: BorrowedFd, name: sys::c_int) -> io::Result<usize> {
+    getsockopt::<sys::c_int>(fd, sys::SOL_SOCKET, name).map(|size| size as usize)
+}
+
+fn tos_option(local_addr: SocketAddr) -> io::Result<(sys::c_int, sys::c_int)> {
+    match local_addr {
+        SocketAddr::V4(_) => Ok((sys::IPPROTO_IP, sys::IP_TOS)),
+        #[cfg(not(target_os = "espidf"))]
+        SocketAddr::V6(_) => Ok((sys::IPPROTO_IPV6, sys::IPV6_TCLASS)),
+        #[cfg(target_os = "espidf")]
+        SocketAddr::V6(_) => Err(io::ErrorKind::Unsupported.into()),
+    }
+}
+
+fn set_tos(fd: BorrowedFd, local_addr: SocketAddr, tos: u32) -> io::Result<()> {
+    let (level, name) = tos_option(local_addr)?;
+
+    setsockopt(fd, level, name, tos as sys::c_int)
+}
+
+fn tos(fd: BorrowedFd, local_addr: SocketAddr) -> io::Result<u32> {
+    let (level, name) = tos_option(local_addr)?;
+
+    getsockopt::<sys::c_int>(fd, level, name).map(|tos| tos as u32)
+}
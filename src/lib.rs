@@ -10,6 +10,14 @@
 //! wake appropriate futures blocked on I/O when they can be resumed.
 //!
 //! To wait for the next I/O event, the task uses the [select] syscall available on many operating systems.
+//! The readiness API is abstracted behind the [`Poller`] trait; the `poll` feature switches the reactor to
+//! the `poll` syscall instead (see [`PollPoller`]), which is not limited to file descriptors below `FD_SETSIZE`.
+//! On macOS and iOS, the reactor uses `kqueue` (see `KqueuePoller`) unless the `poll` feature is enabled.
+//!
+//! The reactor is woken up from other threads via a notification file descriptor, which is an `eventfd` by default.
+//! On the ESP-IDF this requires the eventfd VFS to be registered (`esp_vfs_eventfd_register`). For sdkconfigs where
+//! that is not possible, the `notify-pipe` feature switches - at compile time - to a "self-pipe" instead (a loopback
+//! UDP socket on the ESP-IDF). On macOS and iOS, which have no `eventfd`, the self-pipe is always used.
 //!
 //! # Examples
 //!
@@ -30,12 +38,43 @@
 #![allow(unknown_lints)]
 #![allow(clippy::needless_maybe_sized)]
 
+pub use enumset::EnumSet;
+
+#[cfg(feature = "blocking")]
+pub use blocking::*;
+pub use cancel::*;
+#[cfg(feature = "framed")]
+pub use framed::*;
 pub use io::*;
+pub use oneshot::*;
+pub use poller::*;
+pub use reactor::*;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use signal::*;
 #[cfg(feature = "embassy-time")]
 pub use timer::*;
+#[cfg(feature = "rustls")]
+pub use tls::*;
+pub use tty::*;
+#[cfg(target_os = "linux")]
+pub use vsock::*;
 
+#[cfg(feature = "blocking")]
+mod blocking;
+mod cancel;
+#[cfg(feature = "framed")]
+mod framed;
 mod io;
+mod oneshot;
+mod poller;
 mod reactor;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod signal;
 mod sys;
 #[cfg(feature = "embassy-time")]
 mod timer;
+#[cfg(feature = "rustls")]
+mod tls;
+mod tty;
+#[cfg(target_os = "linux")]
+mod vsock;
@@ -0,0 +1,148 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use crate::reactor::REACTOR;
+
+/// Creates a oneshot channel for signaling a single value from one task to another.
+///
+/// The [`Receiver`] is a future which resolves once [`Sender::send`] is called. The wakeup of the receiving task
+/// is routed through the reactor's notification (when the reactor is running), so the sender never runs the
+/// receiver's waker inline, and an executor parked on the reactor is woken up.
+///
+/// # Examples
+///
+/// ```
+/// use async_io_mini::oneshot;
+///
+/// # futures_lite::future::block_on(async {
+/// let (sender, receiver) = oneshot();
+///
+/// std::thread::spawn(move || {
+///     let _ = sender.send("config reloaded");
+/// });
+///
+/// assert_eq!(receiver.await?, "config reloaded");
+/// # std::io::Result::Ok(()) });
+/// ```
+pub fn oneshot<T>() -> (Sender<T>, Receiver<T>) {
+    let state = Arc::new(Mutex::new(State {
+        value: None,
+        waker: None,
+        sender_dropped: false,
+        receiver_dropped: false,
+    }));
+
+    (Sender(state.clone()), Receiver(state))
+}
+
+#[derive(Debug)]
+struct State<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+    sender_dropped: bool,
+    receiver_dropped: bool,
+}
+
+/// The sending half of a [`oneshot`] channel.
+#[derive(Debug)]
+pub struct Sender<T>(Arc<Mutex<State<T>>>);
+
+impl<T> Sender<T> {
+    /// Sends the value to the [`Receiver`], waking it.
+    ///
+    /// Returns the value back if the receiver has already been dropped.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let mut state = self.0.lock().unwrap();
+
+        if state.receiver_dropped {
+            return Err(value);
+        }
+
+        state.value = Some(value);
+
+        // The waker is taken by `Drop`, once the value is in place
+        Ok(())
+    }
+
+    /// Returns `true` if the [`Receiver`] has been dropped, i.e. sending is pointless.
+    pub fn is_closed(&self) -> bool {
+        self.0.lock().unwrap().receiver_dropped
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.0.lock().unwrap();
+
+            state.sender_dropped = true;
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            REACTOR.wake(waker);
+        }
+    }
+}
+
+/// The receiving half of a [`oneshot`] channel.
+///
+/// Resolves to the sent value, or to an error of kind [`io::ErrorKind::BrokenPipe`] if the [`Sender`] is
+/// dropped without sending a value.
+#[derive(Debug)]
+pub struct Receiver<T>(Arc<Mutex<State<T>>>);
+
+impl<T> Receiver<T> {
+    /// Returns the sent value if it is already available, without waiting.
+    ///
+    /// Returns `Ok(None)` if the value has not been sent yet, and an error of kind [`io::ErrorKind::BrokenPipe`]
+    /// if the [`Sender`] was dropped without sending a value.
+    pub fn try_recv(&mut self) -> io::Result<Option<T>> {
+        let mut state = self.0.lock().unwrap();
+
+        if let Some(value) = state.value.take() {
+            Ok(Some(value))
+        } else if state.sender_dropped {
+            Err(io::ErrorKind::BrokenPipe.into())
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = io::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.lock().unwrap();
+
+        if let Some(value) = state.value.take() {
+            Poll::Ready(Ok(value))
+        } else if state.sender_dropped {
+            Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+        } else {
+            if !state
+                .waker
+                .as_ref()
+                .is_some_and(|waker| waker.will_wake(cx.waker()))
+            {
+                state.waker = Some(cx.waker().clone());
+            }
+
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock().unwrap();
+
+        state.receiver_dropped = true;
+        state.waker = None;
+    }
+}
@@ -0,0 +1,661 @@
+use core::mem::MaybeUninit;
+use core::time::Duration;
+
+use std::io;
+use std::os::fd::RawFd;
+
+use enumset::EnumSet;
+
+use libc as sys;
+
+#[cfg(not(feature = "tracing"))]
+use log::trace;
+#[cfg(feature = "tracing")]
+use tracing::trace;
+
+use crate::reactor::Event;
+use crate::syscall_los;
+
+/// A readiness event reported by a [`Poller`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadyEvent {
+    /// The file descriptor which is ready.
+    pub fd: RawFd,
+    /// The events the file descriptor is ready for.
+    pub events: EnumSet<Event>,
+}
+
+/// The OS readiness API (e.g. `select` or `poll`) used by a [`Reactor`](crate::Reactor)
+/// or a [`LocalReactor`](crate::LocalReactor) to wait for I/O events.
+///
+/// The reactor keeps the interest of each file descriptor in sync with the poller (with [`Poller::add`],
+/// [`Poller::modify`] and [`Poller::delete`]) right before each [`Poller::wait`] call, so the poller only
+/// needs to track the readiness interest; the wakers stay with the reactor.
+///
+/// The pollers are level-triggered: a file descriptor is reported on every wait for as long as it is ready for
+/// any of the events of interest. Exceptional conditions (e.g. a pending socket error or a hangup) should be
+/// reported as ready for all events, so that they are observed by both readers and writers.
+pub trait Poller {
+    /// The file descriptors at or above this limit cannot be watched by the poller, and are therefore refused
+    /// by the reactor already when registered.
+    ///
+    /// Defaults to `None`, i.e. no limit.
+    const FD_LIMIT: Option<RawFd> = None;
+
+    /// Starts watching the file descriptor for the provided events.
+    ///
+    /// The interest might be empty, in which case the file descriptor is tracked, but not watched for anything.
+    fn add(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()>;
+
+    /// Changes the events the (already added) file descriptor is watched for.
+    fn modify(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()>;
+
+    /// Stops watching the file descriptor.
+    ///
+    /// The file descriptor might already be closed at this point.
+    fn delete(&mut self, fd: RawFd) -> io::Result<()>;
+
+    /// Waits (up to `timeout`, or indefinitely if `None`) for at least one watched file descriptor to become ready,
+    /// and returns the ready file descriptors.
+    ///
+    /// Returns an empty slice if the wait timed out.
+    fn wait(&mut self, timeout: Option<Duration>) -> io::Result<&[ReadyEvent]>;
+}
+
+/// A [`Poller`] based on the `select` syscall. This is the default poller.
+///
+/// Only file descriptors below `FD_SETSIZE` can be watched. At most `N` ready file descriptors are reported
+/// per wait; as the poller is level-triggered, the remaining ones are reported by the subsequent waits.
+pub struct SelectPoller<const N: usize> {
+    template: Fds,
+    fds: Fds,
+    initialized: bool,
+    max: Option<RawFd>,
+    ready: heapless::Vec<ReadyEvent, N>,
+}
+
+impl<const N: usize> SelectPoller<N> {
+    /// Creates a new `select` poller.
+    pub const fn new() -> Self {
+        Self {
+            template: Fds::new(),
+            fds: Fds::new(),
+            initialized: false,
+            max: None,
+            ready: heapless::Vec::new(),
+        }
+    }
+
+    fn template(&mut self) -> &mut Fds {
+        if !self.initialized {
+            self.template.zero();
+            self.initialized = true;
+        }
+
+        &mut self.template
+    }
+
+    fn set(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()> {
+        if fd < 0 || fd as usize >= sys::FD_SETSIZE {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the select() poller cannot watch file descriptors above FD_SETSIZE",
+            ))?;
+        }
+
+        let template = self.template();
+
+        for event in EnumSet::<Event>::all() {
+            if interest.contains(event) {
+                template.set(fd, event);
+            } else {
+                template.clear(fd, event);
+            }
+        }
+
+        if interest.is_empty() {
+            template.clear_except(fd);
+
+            if self.max == Some(fd) {
+                // Idle file descriptors should not widen the range scanned by `select`
+                self.max = (0..fd).rev().find(|fd| self.template.is_except(*fd));
+            }
+        } else {
+            // Also watch for exceptional conditions (e.g. pending socket errors), so that
+            // these are surfaced to both readers and writers
+            template.set_except(fd);
+
+            self.max = Some(self.max.map_or(fd, |max| max.max(fd)));
+        }
+
+        trace!("Set FD: {fd}/{interest:?}, max FD: {:?}", self.max);
+
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for SelectPoller<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Poller for SelectPoller<N> {
+    const FD_LIMIT: Option<RawFd> = Some(sys::FD_SETSIZE as RawFd);
+
+    fn add(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()> {
+        self.set(fd, interest)
+    }
+
+    fn modify(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()> {
+        self.set(fd, interest)
+    }
+
+    fn delete(&mut self, fd: RawFd) -> io::Result<()> {
+        self.set(fd, EnumSet::empty())
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> io::Result<&[ReadyEvent]> {
+        self.template();
+        self.fds.clone_from(&self.template);
+        self.ready.clear();
+
+        let max = self.max;
+
+        let count = select(max, &mut self.fds, timeout)?;
+
+        if count > 0 {
+            for fd in 0..=max.unwrap_or(-1) {
+                let events = if self.fds.is_except(fd) {
+                    trace!("FD has an exceptional condition: {fd}");
+
+                    EnumSet::all()
+                } else {
+                    EnumSet::<Event>::all()
+                        .iter()
+                        .filter(|event| self.fds.is_set(fd, *event))
+                        .collect()
+                };
+
+                if !events.is_empty() && self.ready.push(ReadyEvent { fd, events }).is_err() {
+                    // The rest are reported by the next wait
+                    break;
+                }
+            }
+        }
+
+        Ok(&self.ready)
+    }
+}
+
+/// A [`Poller`] based on the `poll` syscall.
+///
+/// Unlike [`SelectPoller`], this poller can watch file descriptors of any value, and its cost does not depend on
+/// the value of the highest watched file descriptor. It can track up to `N` file descriptors.
+pub struct PollPoller<const N: usize> {
+    fds: heapless::Vec<sys::pollfd, N>,
+    ready: heapless::Vec<ReadyEvent, N>,
+}
+
+impl<const N: usize> PollPoller<N> {
+    /// Creates a new `poll` poller.
+    pub const fn new() -> Self {
+        Self {
+            fds: heapless::Vec::new(),
+            ready: heapless::Vec::new(),
+        }
+    }
+
+    fn position(&self, fd: RawFd) -> io::Result<usize> {
+        self.fds
+            .iter()
+            .position(|pollfd| pollfd_fd(pollfd) == fd)
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+}
+
+impl<const N: usize> Default for PollPoller<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Poller for PollPoller<N> {
+    fn add(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()> {
+        self.fds
+            .push(pollfd(fd, interest))
+            .map_err(|_| io::ErrorKind::OutOfMemory)?;
+
+        Ok(())
+    }
+
+    fn modify(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()> {
+        let index = self.position(fd)?;
+
+        self.fds[index] = pollfd(fd, interest);
+
+        Ok(())
+    }
+
+    fn delete(&mut self, fd: RawFd) -> io::Result<()> {
+        let index = self.position(fd)?;
+
+        self.fds.swap_remove(index);
+
+        Ok(())
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> io::Result<&[ReadyEvent]> {
+        self.ready.clear();
+
+        // Round up, so that the reactor does not wake up (and spin) just before a deadline
+        let timeout = timeout.map_or(-1, |timeout| {
+            timeout
+                .as_nanos()
+                .div_ceil(1_000_000)
+                .min(sys::c_int::MAX as u128) as sys::c_int
+        });
+
+        trace!("Start poll");
+
+        let count = syscall_los!(unsafe {
+            sys::poll(self.fds.as_mut_ptr(), self.fds.len() as _, timeout)
+        })?;
+
+        trace!("End poll");
+
+        if count > 0 {
+            for pollfd in &self.fds {
+                if pollfd.revents & sys::POLLNVAL != 0 {
+                    // Consistently with `select`, which fails on closed file descriptors
+                    Err(io::Error::from_raw_os_error(sys::EBADF))?;
+                }
+
+                let events = Event::from_poll_revents(pollfd.revents);
+
+                if !events.is_empty() {
+                    // Cannot fail, as there are no more ready events than file descriptors
+                    let _ = self.ready.push(ReadyEvent {
+                        fd: pollfd.fd,
+                        events,
+                    });
+                }
+            }
+        }
+
+        Ok(&self.ready)
+    }
+}
+
+/// A [`Poller`] based on the `kqueue` and `kevent` syscalls of macOS, iOS and the BSDs.
+///
+/// [`Event::Read`] and [`Event::Write`] are watched with the `EVFILT_READ` and `EVFILT_WRITE` filters respectively.
+/// Consistently with the other pollers, an `EV_EOF` (e.g. a hangup or a pending socket error) signals both events.
+///
+/// Like [`PollPoller`], this poller can watch file descriptors of any value. It can track up to `N` file
+/// descriptors. The kqueue file descriptor is created lazily, on first use.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+pub struct KqueuePoller<const N: usize> {
+    kqueue: Option<std::os::fd::OwnedFd>,
+    interests: heapless::Vec<(RawFd, EnumSet<Event>), N>,
+    events: [MaybeUninit<sys::kevent>; N],
+    ready: heapless::Vec<ReadyEvent, N>,
+}
+
+// The `udata` pointers of the `kevent` structures are never set, nor dereferenced
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+unsafe impl<const N: usize> Send for KqueuePoller<N> {}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+impl<const N: usize> KqueuePoller<N> {
+    /// Creates a new `kqueue` poller.
+    pub const fn new() -> Self {
+        Self {
+            kqueue: None,
+            interests: heapless::Vec::new(),
+            events: [const { MaybeUninit::uninit() }; N],
+            ready: heapless::Vec::new(),
+        }
+    }
+
+    fn kqueue(&mut self) -> io::Result<RawFd> {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        if self.kqueue.is_none() {
+            let kqueue = unsafe { OwnedFd::from_raw_fd(syscall_los!(sys::kqueue())?) };
+
+            syscall_los!(unsafe { sys::fcntl(kqueue.as_raw_fd(), sys::F_SETFD, sys::FD_CLOEXEC) })?;
+
+            trace!("Created kqueue FD: {}", kqueue.as_raw_fd());
+
+            self.kqueue = Some(kqueue);
+        }
+
+        Ok(self.kqueue.as_ref().unwrap().as_raw_fd())
+    }
+
+    fn position(&self, fd: RawFd) -> io::Result<usize> {
+        self.interests
+            .iter()
+            .position(|(interest_fd, _)| *interest_fd == fd)
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    /// Adds the filters of the events in `new`, but not in `old`, and deletes the filters of the events in `old`,
+    /// but not in `new`.
+    ///
+    /// The kernel removes the filters of a file descriptor by itself when the file descriptor is closed, so when
+    /// `closing`, failures to delete filters which are no longer there are ignored.
+    fn change(
+        &mut self,
+        fd: RawFd,
+        old: EnumSet<Event>,
+        new: EnumSet<Event>,
+        closing: bool,
+    ) -> io::Result<()> {
+        let kqueue = self.kqueue()?;
+
+        let mut changes: [sys::kevent; 2] = unsafe { core::mem::zeroed() };
+        let mut len = 0;
+
+        for event in EnumSet::<Event>::all() {
+            let flags = match (old.contains(event), new.contains(event)) {
+                (false, true) => sys::EV_ADD,
+                (true, false) => sys::EV_DELETE,
+                _ => continue,
+            };
+
+            let change = &mut changes[len];
+
+            change.ident = fd as _;
+            change.filter = match event {
+                Event::Read => sys::EVFILT_READ,
+                Event::Write => sys::EVFILT_WRITE,
+            };
+            // Report the outcome of each change in the event list, rather than failing on the first one
+            change.flags = flags | sys::EV_RECEIPT;
+
+            len += 1;
+        }
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        let mut results: [sys::kevent; 2] = unsafe { core::mem::zeroed() };
+
+        let count = syscall_los!(unsafe {
+            sys::kevent(
+                kqueue,
+                changes.as_ptr(),
+                len as _,
+                results.as_mut_ptr(),
+                len as _,
+                core::ptr::null(),
+            )
+        })?;
+
+        for result in &results[..count as usize] {
+            if result.flags & sys::EV_ERROR != 0 && result.data != 0 {
+                let errno = result.data as i32;
+
+                if !(closing && (errno == sys::ENOENT || errno == sys::EBADF)) {
+                    Err(io::Error::from_raw_os_error(errno))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+impl<const N: usize> Default for KqueuePoller<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+impl<const N: usize> Poller for KqueuePoller<N> {
+    fn add(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()> {
+        if self.interests.is_full() {
+            Err(io::ErrorKind::OutOfMemory)?;
+        }
+
+        self.change(fd, EnumSet::empty(), interest, false)?;
+
+        // Cannot fail, as checked above
+        let _ = self.interests.push((fd, interest));
+
+        Ok(())
+    }
+
+    fn modify(&mut self, fd: RawFd, interest: EnumSet<Event>) -> io::Result<()> {
+        let index = self.position(fd)?;
+
+        self.change(fd, self.interests[index].1, interest, false)?;
+
+        self.interests[index].1 = interest;
+
+        Ok(())
+    }
+
+    fn delete(&mut self, fd: RawFd) -> io::Result<()> {
+        let index = self.position(fd)?;
+
+        let (_, interest) = self.interests.swap_remove(index);
+
+        self.change(fd, interest, EnumSet::empty(), true)
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> io::Result<&[ReadyEvent]> {
+        let kqueue = self.kqueue()?;
+
+        self.ready.clear();
+
+        let timeout = timeout.map(|timeout| sys::timespec {
+            tv_sec: timeout.as_secs().min(sys::time_t::MAX as u64) as _,
+            tv_nsec: timeout.subsec_nanos() as _,
+        });
+
+        trace!("Start kevent");
+
+        let count = syscall_los!(unsafe {
+            sys::kevent(
+                kqueue,
+                core::ptr::null(),
+                0,
+                self.events.as_mut_ptr() as *mut sys::kevent,
+                N as _,
+                timeout
+                    .as_ref()
+                    .map(|timeout| timeout as *const _)
+                    .unwrap_or(core::ptr::null()),
+            )
+        })?;
+
+        trace!("End kevent");
+
+        for event in &self.events[..count as usize] {
+            let event = unsafe { event.assume_init_ref() };
+
+            let fd = event.ident as RawFd;
+
+            let events = if event.flags & sys::EV_EOF != 0 {
+                EnumSet::all()
+            } else if event.filter == sys::EVFILT_READ {
+                Event::Read.into()
+            } else if event.filter == sys::EVFILT_WRITE {
+                Event::Write.into()
+            } else {
+                continue;
+            };
+
+            // The read and the write filters of a file descriptor are reported as separate events
+            if let Some(ready) = self.ready.iter_mut().find(|ready| ready.fd == fd) {
+                ready.events |= events;
+            } else {
+                // Cannot fail, as there are no more ready file descriptors than events
+                let _ = self.ready.push(ReadyEvent { fd, events });
+            }
+        }
+
+        Ok(&self.ready)
+    }
+}
+
+/// Creates the `pollfd` of the file descriptor.
+///
+/// File descriptors without any interest are stored negated (as `!fd`), because `poll` ignores negative file
+/// descriptors, but would otherwise still report hangups and errors for them.
+fn pollfd(fd: RawFd, interest: EnumSet<Event>) -> sys::pollfd {
+    sys::pollfd {
+        fd: if interest.is_empty() { !fd } else { fd },
+        events: interest
+            .iter()
+            .fold(0, |flags, event| flags | event.to_poll_flags()),
+        revents: 0,
+    }
+}
+
+fn pollfd_fd(pollfd: &sys::pollfd) -> RawFd {
+    if pollfd.fd < 0 {
+        !pollfd.fd
+    } else {
+        pollfd.fd
+    }
+}
+
+#[derive(Clone)]
+struct Fds {
+    read: MaybeUninit<sys::fd_set>,
+    write: MaybeUninit<sys::fd_set>,
+    except: MaybeUninit<sys::fd_set>,
+}
+
+impl Fds {
+    const fn new() -> Self {
+        Self {
+            read: MaybeUninit::uninit(),
+            write: MaybeUninit::uninit(),
+            except: MaybeUninit::uninit(),
+        }
+    }
+
+    fn zero(&mut self) {
+        unsafe {
+            sys::FD_ZERO(self.read.as_mut_ptr());
+            sys::FD_ZERO(self.write.as_mut_ptr());
+            sys::FD_ZERO(self.except.as_mut_ptr());
+        }
+    }
+
+    fn is_set(&self, fd: RawFd, event: Event) -> bool {
+        unsafe { sys::FD_ISSET(fd, self.fd_set(event)) }
+    }
+
+    fn set(&mut self, fd: RawFd, event: Event) {
+        unsafe { sys::FD_SET(fd, self.fd_set_mut(event)) }
+    }
+
+    fn clear(&mut self, fd: RawFd, event: Event) {
+        unsafe { sys::FD_CLR(fd, self.fd_set_mut(event)) }
+    }
+
+    fn is_except(&self, fd: RawFd) -> bool {
+        unsafe { sys::FD_ISSET(fd, self.except.assume_init_ref()) }
+    }
+
+    fn set_except(&mut self, fd: RawFd) {
+        unsafe { sys::FD_SET(fd, self.except.assume_init_mut()) }
+    }
+
+    fn clear_except(&mut self, fd: RawFd) {
+        unsafe { sys::FD_CLR(fd, self.except.assume_init_mut()) }
+    }
+
+    fn fd_set(&self, event: Event) -> &sys::fd_set {
+        unsafe {
+            match event {
+                Event::Read => self.read.assume_init_ref(),
+                Event::Write => self.write.assume_init_ref(),
+            }
+        }
+    }
+
+    fn fd_set_mut(&mut self, event: Event) -> &mut sys::fd_set {
+        unsafe {
+            match event {
+                Event::Read => self.read.assume_init_mut(),
+                Event::Write => self.write.assume_init_mut(),
+            }
+        }
+    }
+}
+
+fn select(max: Option<RawFd>, fds: &mut Fds, timeout: Option<Duration>) -> io::Result<usize> {
+    let mut timeout = timeout.map(|timeout| {
+        // Round up, so that the reactor does not wake up (and spin) just before a deadline
+        let micros = timeout.as_nanos().div_ceil(1000);
+
+        sys::timeval {
+            tv_sec: (micros / 1_000_000).min(sys::time_t::MAX as u128) as _,
+            tv_usec: (micros % 1_000_000) as _,
+        }
+    });
+
+    trace!("Start select");
+
+    let result = syscall_los!(unsafe {
+        sys::select(
+            max.map_or(0, |max| max + 1),
+            fds.read.assume_init_mut(),
+            fds.write.assume_init_mut(),
+            fds.except.assume_init_mut(),
+            timeout
+                .as_mut()
+                .map(|timeout| timeout as *mut _)
+                .unwrap_or(core::ptr::null_mut()),
+        )
+    });
+
+    trace!("End select");
+
+    result.map(|count| count as usize)
+}
@@ -1,76 +1,261 @@
+use core::future::{poll_fn, Future};
 use core::mem::MaybeUninit;
 use core::sync::atomic::{AtomicBool, Ordering};
-use core::task::Waker;
+use core::task::{Poll, Waker};
+use core::time::Duration;
 
 use std::io::{self, ErrorKind};
-use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 use std::sync::MutexGuard;
+use std::time::Instant;
 
 use enumset::{EnumSet, EnumSetType};
 
-use log::{debug, info, trace};
+#[cfg(not(feature = "tracing"))]
+use log::{debug, error, info, trace, warn};
+#[cfg(feature = "tracing")]
+use tracing::{debug, error, info, trace, warn};
 
 use libc as sys;
 
-use crate::{syscall, syscall_los, syscall_los_eagain};
+use crate::poller::{Poller, ReadyEvent, SelectPoller};
+use crate::{syscall_los, syscall_los_eagain};
 
 // In future, we might want to use a smaller - and possibly - configurable - with cargo feature(s)
 // amount of registrations to save memory, but for now, let's use the maximum amount
 const MAX_REGISTRATIONS: usize = sys::FD_SETSIZE;
 
-#[derive(EnumSetType, Debug)]
-pub(crate) enum Event {
-    Read = 0,
-    Write = 1,
+/// Configuration of the [`Reactor`].
+///
+/// The configuration is applied when the reactor is started with [`Reactor::start_with_config`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ReactorConfig {
+    /// When set, the reactor waits for the given duration after it was woken up by a notification
+    /// (i.e. a registration or an interest change), so that a burst of notifications is coalesced
+    /// into a single `select` pass.
+    ///
+    /// This trades a bit of latency for fewer reactor wakeups, which might be important when the
+    /// CPU is trying to stay in light sleep.
+    ///
+    /// Defaults to `None`, i.e. notifications are processed immediately.
+    pub notify_debounce: Option<Duration>,
+    /// When set, the reactor wakes at most that many wakers in a single pass. The remaining ready file descriptors
+    /// keep their wakers armed and are woken in the subsequent passes, starting with the ones which were
+    /// left out, so that all file descriptors are eventually served.
+    ///
+    /// Under heavy load, this returns control to the executor (and e.g. to its timers) sooner, trading some
+    /// throughput for latency fairness.
+    ///
+    /// Defaults to `None`, i.e. all ready wakers are woken in the same pass.
+    pub max_wakes_per_pass: Option<usize>,
+    /// When set, the reactor logs a warning once the number of registrations reaches this high-water mark
+    /// (e.g. 80% of the reactor capacity).
+    ///
+    /// The warning is logged once per crossing, i.e. it is logged again only after the number of registrations
+    /// has dropped below the mark. This is an early signal of leaking file descriptors, well before the
+    /// reactor starts refusing new registrations.
+    ///
+    /// Defaults to `None`, i.e. no warning is logged.
+    pub capacity_warn_threshold: Option<usize>,
+    /// When set, registration and interest changes wait at most that long for the reactor to acknowledge them,
+    /// failing with an error of kind [`io::ErrorKind::TimedOut`] afterwards.
+    ///
+    /// This turns a wedged reactor (e.g. a reactor thread stuck in a buggy callback) into an observable failure
+    /// rather than a silent hang. Note that a timed out change is still applied, but the reactor might not have
+    /// picked it up yet. [`Async`](crate::Async) undoes its registration when it times out, so that its file
+    /// descriptor is never closed while still registered.
+    ///
+    /// Defaults to `None`, i.e. waiting forever.
+    pub modify_timeout: Option<Duration>,
+    /// When set, the reactor logs a warning whenever a registration or an interest change holds the internal
+    /// lock of the reactor for longer than that (e.g. a waker whose `clone` or `wake` is slow, or a callback
+    /// registered from a busy thread).
+    ///
+    /// As all I/O goes through this lock, such changes cause latency spikes across all file descriptors.
+    /// See also [`ReactorStats::modify_hold_max`].
+    ///
+    /// Only available with the `metrics` feature. Defaults to `None`, i.e. no warning is logged.
+    #[cfg(feature = "metrics")]
+    pub slow_modify_threshold: Option<Duration>,
+    /// When set, the reactor calls this function whenever it becomes active (`true`), i.e. when it picks up
+    /// the first registration, and whenever it becomes idle again (`false`), i.e. once the last registration is gone.
+    ///
+    /// This allows powering peripherals (e.g. the WiFi radio) only while there is async I/O to serve.
+    ///
+    /// The function is called on the reactor thread (or the thread calling [`Reactor::poll_once`]), during
+    /// the reactor pass which picks up the change, while the reactor is holding its internal lock. Therefore,
+    /// the function must not block, and must not call back into the reactor (e.g. [`Reactor::register`]
+    /// or [`Reactor::deregister`]), as that would deadlock the reactor.
+    ///
+    /// Defaults to `None`.
+    pub on_activity_change: Option<fn(bool)>,
+    /// The initial value of the counter of the notification eventfd.
+    ///
+    /// A non-zero value makes the reactor start with pending notifications, i.e. with that many (in semaphore
+    /// mode) or a single (otherwise) spurious pass.
+    ///
+    /// Ignored when the notifications go through a self-pipe instead (the `notify-pipe` feature, and on Apple
+    /// targets). Defaults to 0.
+    pub eventfd_initval: core::ffi::c_uint,
+    /// Additional flags of the notification eventfd, e.g. `EFD_SEMAPHORE`.
+    ///
+    /// In semaphore mode, each reactor pass consumes a single notification, i.e. decrements the counter by one,
+    /// rather than resetting it. The eventfd stays readable until all notifications are consumed, so the
+    /// reactor does one pass per notification, rather than one pass per burst of notifications.
+    ///
+    /// Off ESP-IDF, `EFD_NONBLOCK` is always added. On ESP-IDF, the flags are passed as they are; note that its
+    /// eventfd implementation has no semaphore mode.
+    ///
+    /// Ignored when the notifications go through a self-pipe instead (the `notify-pipe` feature, and on Apple
+    /// targets). Defaults to 0.
+    pub eventfd_flags: core::ffi::c_int,
+}
+
+impl ReactorConfig {
+    /// Creates a new configuration with the default settings.
+    pub const fn new() -> Self {
+        Self {
+            notify_debounce: None,
+            max_wakes_per_pass: None,
+            capacity_warn_threshold: None,
+            modify_timeout: None,
+            #[cfg(feature = "metrics")]
+            slow_modify_threshold: None,
+            on_activity_change: None,
+            eventfd_initval: 0,
+            eventfd_flags: 0,
+        }
+    }
 }
 
-struct Fds {
-    read: MaybeUninit<sys::fd_set>,
-    write: MaybeUninit<sys::fd_set>,
-    except: MaybeUninit<sys::fd_set>,
+impl Default for ReactorConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Statistics collected by the [`Reactor`].
+///
+/// Only available with the `metrics` feature. The latencies are measured with the monotonic clock
+/// and cost a couple of `clock_gettime` calls per reactor pass.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReactorStats {
+    /// The number of completed reactor passes.
+    pub passes: u64,
+    /// The latency between the last notification of the reactor (i.e. a registration or an interest change)
+    /// and the reactor completing the processing of its events.
+    pub notify_latency_last: Duration,
+    /// The maximum observed latency between a notification of the reactor and the reactor completing
+    /// the processing of its events.
+    pub notify_latency_max: Duration,
+    /// The latency between the last `select` call returning and the reactor waking the wakers of the ready
+    /// file descriptors.
+    pub wake_latency_last: Duration,
+    /// The maximum observed latency between a `select` call returning and the reactor waking the wakers
+    /// of the ready file descriptors.
+    pub wake_latency_max: Duration,
+    /// The highest file descriptor watched by the last poller wait (i.e. the `nfds` argument of `select`
+    /// minus one), or `None` if no file descriptor was watched.
+    pub max_fd: Option<RawFd>,
+    /// The time the last registration or interest change held the internal lock of the reactor for.
+    pub modify_hold_last: Duration,
+    /// The maximum observed time a registration or an interest change held the internal lock of the reactor for.
+    pub modify_hold_max: Duration,
 }
 
-impl Fds {
+#[cfg(feature = "metrics")]
+impl ReactorStats {
     const fn new() -> Self {
         Self {
-            read: MaybeUninit::uninit(),
-            write: MaybeUninit::uninit(),
-            except: MaybeUninit::uninit(),
+            passes: 0,
+            notify_latency_last: Duration::ZERO,
+            notify_latency_max: Duration::ZERO,
+            wake_latency_last: Duration::ZERO,
+            wake_latency_max: Duration::ZERO,
+            max_fd: None,
+            modify_hold_last: Duration::ZERO,
+            modify_hold_max: Duration::ZERO,
         }
     }
 
-    fn zero(&mut self) {
-        unsafe {
-            sys::FD_ZERO(self.read.as_mut_ptr());
-            sys::FD_ZERO(self.write.as_mut_ptr());
-            sys::FD_ZERO(self.except.as_mut_ptr());
+    fn record_pass(&mut self, select_end: Instant, notified_at: Option<Instant>) {
+        let now = Instant::now();
+
+        self.passes += 1;
+
+        self.wake_latency_last = now.saturating_duration_since(select_end);
+        self.wake_latency_max = self.wake_latency_max.max(self.wake_latency_last);
+
+        if let Some(notified_at) = notified_at {
+            self.notify_latency_last = now.saturating_duration_since(notified_at);
+            self.notify_latency_max = self.notify_latency_max.max(self.notify_latency_last);
         }
     }
+}
 
-    fn is_set(&self, fd: RawFd, event: Event) -> bool {
-        unsafe { sys::FD_ISSET(fd, self.fd_set(event)) }
-    }
+/// An I/O readiness event.
+#[derive(EnumSetType, Debug)]
+pub enum Event {
+    /// The file descriptor is readable.
+    Read = 0,
+    /// The file descriptor is writable.
+    Write = 1,
+}
 
-    fn set(&mut self, fd: RawFd, event: Event) {
-        unsafe { sys::FD_SET(fd, self.fd_set_mut(event)) }
+impl Event {
+    /// Returns the `poll` flags requesting this event.
+    ///
+    /// The reactor watches [`Event::Read`] with the read `fd_set` of `select` (equivalent to `POLLIN`),
+    /// and [`Event::Write`] with the write `fd_set` (equivalent to `POLLOUT`).
+    pub const fn to_poll_flags(self) -> sys::c_short {
+        match self {
+            Self::Read => sys::POLLIN,
+            Self::Write => sys::POLLOUT,
+        }
     }
 
-    fn fd_set(&self, event: Event) -> &sys::fd_set {
-        unsafe {
-            match event {
-                Event::Read => self.read.assume_init_ref(),
-                Event::Write => self.write.assume_init_ref(),
-            }
+    /// Returns the events signalled by the provided `poll` returned events (`revents`) flags.
+    ///
+    /// Consistently with the reactor, which wakes both readers and writers on exceptional conditions,
+    /// `POLLERR` and `POLLHUP` signal both [`Event::Read`] and [`Event::Write`], so that a pending error
+    /// or a hangup is observed by whoever operates on the file descriptor next.
+    pub fn from_poll_revents(revents: sys::c_short) -> EnumSet<Event> {
+        let mut events = EnumSet::empty();
+
+        if revents & (sys::POLLIN | sys::POLLPRI) != 0 {
+            events |= Self::Read;
         }
-    }
 
-    fn fd_set_mut(&mut self, event: Event) -> &mut sys::fd_set {
-        unsafe {
-            match event {
-                Event::Read => self.read.assume_init_mut(),
-                Event::Write => self.write.assume_init_mut(),
-            }
+        if revents & sys::POLLOUT != 0 {
+            events |= Self::Write;
+        }
+
+        if revents & (sys::POLLERR | sys::POLLHUP) != 0 {
+            events |= Self::Read | Self::Write;
         }
+
+        events
+    }
+}
+
+/// A "fetch the readiness, or else arm the waker" operation of [`Reactor::modify_batch`].
+#[derive(Debug, Clone, Copy)]
+pub struct InterestOp<'a> {
+    /// The registered file descriptor.
+    pub fd: RawFd,
+    /// The event to wait for.
+    pub event: Event,
+    /// The waker to wake when the file descriptor becomes ready for the event.
+    pub waker: &'a Waker,
+}
+
+impl<'a> InterestOp<'a> {
+    /// Creates a new operation.
+    pub const fn new(fd: RawFd, event: Event, waker: &'a Waker) -> Self {
+        Self { fd, event, waker }
     }
 }
 
@@ -78,24 +263,160 @@ struct Registration {
     fd: RawFd,
     events: EnumSet<Event>,
     wakers: [Option<Waker>; 2],
+    callbacks: EnumSet<Event>,
+    deadlines: [Option<Instant>; 2],
+    identity: Option<FdIdentity>,
+    reserved: bool,
+    data: usize,
+    /// The interest last added to the poller; `None` if not added yet
+    polled: Option<EnumSet<Event>>,
+    /// Whether each event is disarmed once reported (see [`Reactor::register_oneshot`])
+    oneshot: bool,
+    /// The events which are not watched and not reported, until re-armed with [`Reactor::set_interest`]
+    disarmed: EnumSet<Event>,
+    /// The events reported by the poller in the current pass; only set while the pass is being processed
+    ready: EnumSet<Event>,
+    /// The number of times the read and the write wakers were woken
+    #[cfg(feature = "metrics")]
+    wakes: [u32; 2],
+}
+
+#[cfg(feature = "metrics")]
+impl Registration {
+    fn count_wake(&mut self, event: Event) {
+        self.wakes[event as usize] = self.wakes[event as usize].wrapping_add(1);
+    }
+}
+
+/// The identity (device and inode) of the file object a file descriptor refers to.
+///
+/// Used to tell apart a duplicate registration of the same file descriptor from a registration of a
+/// recycled file descriptor number, whose previous owner was closed without being deregistered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct FdIdentity {
+    dev: sys::dev_t,
+    ino: sys::ino_t,
+}
+
+impl FdIdentity {
+    fn of(fd: RawFd) -> io::Result<Option<Self>> {
+        let mut stat = MaybeUninit::<sys::stat>::uninit();
+
+        syscall_los!(unsafe { sys::fstat(fd, stat.as_mut_ptr()) })?;
+
+        let stat = unsafe { stat.assume_init() };
+
+        // Some platforms (e.g. the ESP-IDF) do not report inode numbers for sockets
+        Ok((stat.st_ino != 0).then_some(Self {
+            dev: stat.st_dev,
+            ino: stat.st_ino,
+        }))
+    }
+}
+
+/// A waker which calls a callback on the reactor thread, rather than waking a task.
+struct Callback(std::sync::Mutex<Box<dyn FnMut() + Send>>);
+
+impl std::task::Wake for Callback {
+    fn wake(self: std::sync::Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &std::sync::Arc<Self>) {
+        (self.0.lock().unwrap())();
+    }
 }
 
 struct Registrations<const N: usize> {
+    /// Sorted by file descriptor, so that the poller events can be matched to them with a binary search
     vec: heapless::Vec<Registration, N>,
     event_fd: Option<OwnedFd>,
+    /// The write end of the notification pipe; `None` if the notification file descriptor is written to directly
+    #[cfg(any(feature = "notify-pipe", target_vendor = "apple"))]
+    notify_fd: Option<OwnedFd>,
     waiting: usize,
+    reserved: usize,
+    capacity_warned: bool,
+    /// Whether there were registrations, as of the last poller sync (see [`ReactorConfig::on_activity_change`])
+    active: bool,
+    config: ReactorConfig,
+    failure: Option<io::Error>,
+    health_waker: Option<Waker>,
+    pass_hook: Option<Box<dyn FnMut() + Send>>,
+    wake_cursor: usize,
+    passes: u64,
+    sync_wakers: Vec<Waker>,
+    yield_wakers: Vec<Waker>,
+    /// The file descriptors which were deregistered after having been added to the poller
+    removed: heapless::Vec<RawFd, N>,
+    /// Whether the notification file descriptor is added to the poller
+    event_fd_polled: bool,
+    /// See [`Poller::FD_LIMIT`]
+    fd_limit: Option<RawFd>,
+    /// Set when the registrations or their armed wakers change, i.e. when the poller needs to be synced
+    dirty: bool,
+    #[cfg(feature = "metrics")]
+    stats: ReactorStats,
+    #[cfg(feature = "metrics")]
+    notified_at: Option<Instant>,
 }
 
 impl<const N: usize> Registrations<N> {
-    const fn new() -> Self {
+    const fn new(fd_limit: Option<RawFd>) -> Self {
         Self {
             vec: heapless::Vec::new(),
             event_fd: None,
+            #[cfg(any(feature = "notify-pipe", target_vendor = "apple"))]
+            notify_fd: None,
             waiting: 0,
+            reserved: 0,
+            capacity_warned: false,
+            active: false,
+            config: ReactorConfig::new(),
+            failure: None,
+            health_waker: None,
+            pass_hook: None,
+            wake_cursor: 0,
+            passes: 0,
+            sync_wakers: Vec::new(),
+            yield_wakers: Vec::new(),
+            removed: heapless::Vec::new(),
+            event_fd_polled: false,
+            fd_limit,
+            dirty: true,
+            #[cfg(feature = "metrics")]
+            stats: ReactorStats::new(),
+            #[cfg(feature = "metrics")]
+            notified_at: None,
+        }
+    }
+
+    fn reserve(&mut self, n: usize) -> io::Result<()> {
+        if n > N {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "cannot reserve more registration slots than the reactor capacity",
+            ))?;
+        }
+
+        if n > N - self.unreserved() {
+            Err(io::Error::new(
+                ErrorKind::OutOfMemory,
+                "not enough free registration slots to reserve",
+            ))?;
         }
+
+        self.reserved = n;
+
+        Ok(())
+    }
+
+    /// Returns the number of registrations which do not use the reserved slots.
+    fn unreserved(&self) -> usize {
+        self.vec.iter().filter(|reg| !reg.reserved).count()
     }
 
-    fn register(&mut self, fd: RawFd) -> io::Result<()> {
+    fn register(&mut self, fd: RawFd, reserved: bool) -> io::Result<()> {
         if fd < 0
             || self
                 .event_fd
@@ -106,22 +427,73 @@ impl<const N: usize> Registrations<N> {
             Err(ErrorKind::InvalidInput)?;
         }
 
-        if fd >= sys::FD_SETSIZE as RawFd {
-            Err(ErrorKind::InvalidInput)?;
+        if self.fd_limit.is_some_and(|fd_limit| fd >= fd_limit) {
+            // Fail early, as the poller would fail on it later, taking down the whole reactor
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "file descriptor is above the limit of the poller (e.g. FD_SETSIZE for `select`)",
+            ))?;
         }
 
-        if self.vec.iter().any(|reg| reg.fd == fd) {
-            Err(ErrorKind::InvalidInput)?;
+        // Fail early on closed file descriptors, as `select` would fail on them later with `EBADF`,
+        // taking down the whole reactor
+        syscall_los!(unsafe { sys::fcntl(fd, sys::F_GETFD) })?;
+
+        let identity = FdIdentity::of(fd)?;
+
+        if let Some(index) = self.vec.iter().position(|reg| reg.fd == fd) {
+            let stale = &self.vec[index];
+
+            if stale.identity.is_none() || stale.identity == identity {
+                Err(io::Error::new(
+                    ErrorKind::AlreadyExists,
+                    "file descriptor is already registered in the reactor",
+                ))?;
+            }
+
+            // The file descriptor number was recycled by the OS: its previous owner was closed
+            // without being deregistered. Reclaim the stale registration, dropping its wakers so that
+            // the readiness of the new file descriptor is not reported to the tasks of the old one
+            warn!("Reclaiming the stale registration of recycled FD {fd}; was it closed without being deregistered?");
+
+            self.remove(index);
+        }
+
+        if !reserved && self.unreserved() >= N - self.reserved {
+            Err(io::Error::new(
+                ErrorKind::OutOfMemory,
+                "no free registration slots (apart from the reserved ones)",
+            ))?;
         }
 
+        let index = self.vec.partition_point(|reg| reg.fd < fd);
+
         self.vec
-            .push(Registration {
-                fd,
-                events: EnumSet::empty(),
-                wakers: [None, None],
-            })
+            .insert(
+                index,
+                Registration {
+                    fd,
+                    events: EnumSet::empty(),
+                    wakers: [None, None],
+                    callbacks: EnumSet::empty(),
+                    deadlines: [None, None],
+                    identity,
+                    reserved,
+                    data: 0,
+                    polled: None,
+                    oneshot: false,
+                    disarmed: EnumSet::empty(),
+                    ready: EnumSet::empty(),
+                    #[cfg(feature = "metrics")]
+                    wakes: [0; 2],
+                },
+            )
             .map_err(|_| ErrorKind::OutOfMemory)?;
 
+        self.dirty = true;
+
+        self.check_capacity();
+
         Ok(())
     }
 
@@ -130,84 +502,117 @@ impl<const N: usize> Registrations<N> {
             return Err(ErrorKind::NotFound.into());
         };
 
-        self.vec.swap_remove(index);
+        self.remove(index);
+
+        self.dirty = true;
+
+        self.check_capacity();
 
         Ok(())
     }
 
-    fn set(&mut self, fd: RawFd, event: Event, waker: &Waker) -> io::Result<()> {
+    fn remove(&mut self, index: usize) {
+        let registration = self.vec.remove(index);
+
+        if registration.polled.is_some() {
+            // Cannot overflow, as only the registrations present at the last poller sync are pushed,
+            // each at most once
+            let _ = self.removed.push(registration.fd);
+        }
+    }
+
+    fn set_readiness(&mut self, fd: RawFd, events: EnumSet<Event>) -> io::Result<()> {
         let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
             return Err(ErrorKind::NotFound.into());
         };
 
-        registration.events.remove(event);
-
-        if let Some(prev_waker) = registration.wakers[event as usize].replace(waker.clone()) {
-            if !prev_waker.will_wake(waker) {
-                prev_waker.wake();
-            }
-        }
+        registration.events |= events;
 
         Ok(())
     }
 
-    fn fetch(&mut self, fd: RawFd, event: Event) -> io::Result<bool> {
+    fn set_data(&mut self, fd: RawFd, data: usize) -> io::Result<()> {
         let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
             return Err(ErrorKind::NotFound.into());
         };
 
-        let set = registration.events.contains(event);
+        registration.data = data;
+
+        Ok(())
+    }
+
+    fn set_oneshot(&mut self, fd: RawFd) -> io::Result<()> {
+        let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
+            return Err(ErrorKind::NotFound.into());
+        };
 
-        registration.events.remove(event);
+        registration.oneshot = true;
 
-        Ok(set)
+        Ok(())
     }
 
-    #[allow(deprecated)]
-    fn set_fds(&self, fds: &mut Fds) -> io::Result<Option<RawFd>> {
-        fds.zero();
+    fn set_interest(&mut self, fd: RawFd, events: EnumSet<Event>) -> io::Result<()> {
+        let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
+            return Err(ErrorKind::NotFound.into());
+        };
 
-        let mut max: Option<RawFd> = None;
+        registration.disarmed = events.complement();
 
-        if let Some(event_fd) = self.event_fd.as_ref().map(|event_fd| event_fd.as_raw_fd()) {
-            fds.set(event_fd, Event::Read);
-            max = Some(max.map_or(event_fd, |max| max.max(event_fd)));
+        self.dirty = true;
 
-            trace!("Set event FD: {event_fd}");
-        }
+        Ok(())
+    }
 
-        for registration in &self.vec {
-            for event in EnumSet::ALL {
-                if registration.wakers[event as usize].is_some() {
-                    fds.set(registration.fd, event);
+    fn data(&self, fd: RawFd) -> io::Result<usize> {
+        self.vec
+            .iter()
+            .find(|reg| reg.fd == fd)
+            .map(|reg| reg.data)
+            .ok_or_else(|| ErrorKind::NotFound.into())
+    }
 
-                    trace!("Set registration FD: {}/{event:?}", registration.fd);
-                }
+    fn check_capacity(&mut self) {
+        let Some(threshold) = self.config.capacity_warn_threshold else {
+            return;
+        };
 
-                max = Some(max.map_or(registration.fd, |max| max.max(registration.fd)));
-            }
-        }
+        if self.vec.len() < threshold {
+            self.capacity_warned = false;
+        } else if !self.capacity_warned {
+            warn!(
+                "Registrations reached the high-water mark: {}/{N} (threshold {threshold})",
+                self.vec.len()
+            );
 
-        trace!("Max FDs: {max:?}");
+            self.capacity_warned = true;
+        }
+    }
 
-        Ok(max)
+    fn set(&mut self, fd: RawFd, event: Event, waker: &Waker) -> io::Result<()> {
+        self.set_multi(fd, event.into(), waker)
     }
 
-    #[allow(deprecated)]
-    fn update_events(&mut self, fds: &Fds) -> io::Result<()> {
-        trace!("Updating events");
+    /// Arms the waker for all of the provided events, with a single lookup of the registration.
+    ///
+    /// Fails with [`ErrorKind::AlreadyExists`] if a callback is registered for any of the events, as the callback
+    /// stays armed until the file descriptor is deregistered.
+    fn set_multi(&mut self, fd: RawFd, events: EnumSet<Event>, waker: &Waker) -> io::Result<()> {
+        let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
+            return Err(ErrorKind::NotFound.into());
+        };
 
-        self.consume_notification()?;
+        if !registration.callbacks.is_disjoint(events) {
+            return Err(ErrorKind::AlreadyExists.into());
+        }
 
-        for registration in &mut self.vec {
-            for event in EnumSet::ALL {
-                if fds.is_set(registration.fd, event) {
-                    trace!("Registration FD is set: {}/{event:?}", registration.fd);
+        registration.events.remove_all(events);
 
-                    registration.events |= event;
-                    if let Some(waker) = registration.wakers[event as usize].take() {
-                        waker.wake();
-                    }
+        self.dirty = true;
+
+        for event in events {
+            if let Some(prev_waker) = registration.wakers[event as usize].replace(waker.clone()) {
+                if !prev_waker.will_wake(waker) {
+                    prev_waker.wake();
                 }
             }
         }
@@ -215,153 +620,1158 @@ impl<const N: usize> Registrations<N> {
         Ok(())
     }
 
-    fn create_notification(&mut self) -> io::Result<bool> {
-        if self.event_fd.is_none() {
-            #[cfg(not(target_os = "espidf"))]
-            let event_fd =
-                unsafe { OwnedFd::from_raw_fd(syscall_los!(sys::eventfd(0, sys::EFD_NONBLOCK))?) };
+    fn set_callback(&mut self, fd: RawFd, event: Event, callback: Waker) -> io::Result<()> {
+        let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
+            return Err(ErrorKind::NotFound.into());
+        };
 
-            // Note that the eventfd() implementation in ESP-IDF deviates from the specification in the following ways:
-            // 1) The file descriptor is always in a non-blocking mode, as if EFD_NONBLOCK was passed as a flag;
-            //    passing EFD_NONBLOCK or calling fcntl(.., F_GETFL/F_SETFL) on the eventfd() file descriptor is not supported
-            // 2) It always returns the counter value, even if it is 0. This is contrary to the specification which mandates
-            //    that it should instead fail with EAGAIN
-            //
-            // (1) is not a problem for us, as we want the eventfd() file descriptor to be in a non-blocking mode anyway
-            // (2) is also not a problem, as long as we don't try to read the counter value in an endless loop when we detect being notified
-            #[cfg(target_os = "espidf")]
-            let event_fd = unsafe {
-                OwnedFd::from_raw_fd(syscall_los!(sys::eventfd(0, 0)).map_err(|err| {
-                    match err {
-                        err if err.kind() == io::ErrorKind::PermissionDenied => {
-                            // EPERM can happen if the eventfd isn't initialized yet.
-                            // Tell the user to call esp_vfs_eventfd_register.
-                            io::Error::new(
-                                io::ErrorKind::PermissionDenied,
-                                "failed to initialize eventfd for polling, try calling `esp_vfs_eventfd_register`"
-                            )
-                        },
-                        err => err,
-                    }
-                })?)
-            };
+        let prev_callback = registration.callbacks.contains(event);
 
-            debug!("Created event FD: {}", event_fd.as_raw_fd());
+        registration.callbacks |= event;
 
-            self.event_fd = Some(event_fd);
+        self.dirty = true;
 
-            Ok(true)
-        } else {
-            Ok(false)
+        if let Some(prev_waker) = registration.wakers[event as usize].replace(callback) {
+            if !prev_callback {
+                prev_waker.wake();
+            }
         }
-    }
 
-    fn destroy_notification(&mut self) -> io::Result<bool> {
-        if let Some(event_fd) = self.event_fd.take() {
-            syscall!(unsafe { sys::close(event_fd.as_raw_fd()) })?;
+        Ok(())
+    }
 
-            debug!("Closed event FD: {}", event_fd.as_raw_fd());
+    fn clear(&mut self, fd: RawFd, events: EnumSet<Event>) -> io::Result<()> {
+        let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
+            return Err(ErrorKind::NotFound.into());
+        };
 
-            Ok(true)
-        } else {
-            Ok(false)
+        // The callbacks stay armed until the file descriptor is deregistered
+        for event in events.difference(registration.callbacks) {
+            registration.wakers[event as usize] = None;
         }
-    }
-
-    fn notify(&self) -> io::Result<bool> {
-        if let Some(event_fd) = self.event_fd.as_ref() {
-            let event_fd = event_fd.as_raw_fd();
 
-            syscall_los_eagain!(unsafe {
-                sys::write(
-                    event_fd,
-                    &u64::to_be_bytes(1_u64) as *const _ as *const _,
-                    core::mem::size_of::<u64>(),
-                )
-            })?;
+        self.dirty = true;
 
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        Ok(())
     }
 
-    fn consume_notification(&mut self) -> io::Result<bool> {
-        if let Some(event_fd) = self.event_fd.as_ref() {
-            let event_fd = event_fd.as_raw_fd();
+    fn set_deadline(
+        &mut self,
+        fd: RawFd,
+        events: EnumSet<Event>,
+        deadline: Option<Instant>,
+    ) -> io::Result<()> {
+        let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
+            return Err(ErrorKind::NotFound.into());
+        };
 
-            let mut buf = [0_u8; core::mem::size_of::<u64>()];
+        for event in events {
+            registration.deadlines[event as usize] = deadline;
+        }
 
-            syscall_los_eagain!(unsafe {
-                sys::read(
-                    event_fd,
-                    &mut buf as *mut _ as *mut _,
-                    core::mem::size_of::<u64>(),
-                )
-            })?;
+        Ok(())
+    }
 
-            trace!("Consumed notification");
+    /// Removes the deadline of those of the provided events, which still have it, i.e. leaves alone the deadlines
+    /// which were since replaced by other waits.
+    fn clear_deadline(
+        &mut self,
+        fd: RawFd,
+        events: EnumSet<Event>,
+        deadline: Instant,
+    ) -> io::Result<()> {
+        let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
+            return Err(ErrorKind::NotFound.into());
+        };
 
-            Ok(true)
-        } else {
-            Ok(false)
+        for event in events {
+            if registration.deadlines[event as usize] == Some(deadline) {
+                registration.deadlines[event as usize] = None;
+            }
         }
+
+        Ok(())
     }
-}
 
-pub struct Reactor<const N: usize> {
-    registrations: std::sync::Mutex<Registrations<N>>,
-    condvar: std::sync::Condvar,
-    started: AtomicBool,
-}
+    fn next_deadline(&self) -> Option<Instant> {
+        self.vec
+            .iter()
+            .flat_map(|registration| registration.deadlines.iter().flatten())
+            .min()
+            .copied()
+    }
 
-impl<const N: usize> Reactor<N> {
-    const fn new() -> Self {
-        Self {
-            registrations: std::sync::Mutex::new(Registrations::new()),
-            condvar: std::sync::Condvar::new(),
-            started: AtomicBool::new(false),
+    /// Returns the timeout of the next `select` call, which honors both the provided timeout (`None` meaning
+    /// an indefinite one) and the earliest registration deadline.
+    ///
+    /// This is the single place where anything which bounds how long the reactor may block plugs in.
+    fn next_timeout(&self, timeout: Option<Duration>) -> Option<Duration> {
+        let until_deadline = self
+            .next_deadline()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+        match (timeout, until_deadline) {
+            (Some(timeout), Some(until_deadline)) => Some(timeout.min(until_deadline)),
+            (timeout, until_deadline) => timeout.or(until_deadline),
         }
     }
 
-    /// Starts the reactor. Returns `false` if it had been already started.
-    pub fn start(&'static self) -> io::Result<bool> {
-        if self.started.swap(true, Ordering::SeqCst) {
-            return Ok(false);
-        }
+    fn fetch(&mut self, fd: RawFd, event: Event) -> io::Result<bool> {
+        Ok(!self.fetch_multi(fd, event.into())?.is_empty())
+    }
 
-        info!("Starting reactor");
+    /// Consumes the readiness for all of the provided events, with a single lookup of the registration.
+    ///
+    /// Returns the events for which the file descriptor was ready.
+    fn fetch_multi(&mut self, fd: RawFd, events: EnumSet<Event>) -> io::Result<EnumSet<Event>> {
+        let Some(registration) = self.vec.iter_mut().find(|reg| reg.fd == fd) else {
+            return Err(ErrorKind::NotFound.into());
+        };
 
-        std::thread::Builder::new()
-            .name("async-io-mini".into())
-            .stack_size(3048)
-            .spawn(move || {
-                self.run().unwrap();
-            })?;
+        let set = registration.events & events;
 
-        Ok(true)
-    }
+        registration.events.remove_all(events);
 
-    pub(crate) fn register(&self, fd: RawFd) -> io::Result<()> {
-        self.modify(|regs| regs.register(fd))
+        Ok(set)
     }
 
-    pub(crate) fn deregister(&self, fd: RawFd) -> io::Result<()> {
-        self.modify(|regs| regs.deregister(fd))
-    }
+    /// Syncs the interest of the registrations with the poller, ahead of the next [`Poller::wait`] call.
+    ///
+    /// The poller is only synced if the registrations or their armed wakers changed since the previous pass.
+    fn sync_poller<P: Poller>(&mut self, poller: &mut P) -> io::Result<()> {
+        if !self.dirty {
+            trace!("Poller is already in sync");
 
-    // pub(crate) fn set(&self, fd: RawFd, event: Event, waker: &Waker) -> io::Result<()> {
-    //     self.lock(|regs| regs.set(fd, event, waker))
-    // }
+            return Ok(());
+        }
 
-    pub(crate) fn fetch(&self, fd: RawFd, event: Event) -> io::Result<bool> {
-        self.modify(|regs| regs.fetch(fd, event))
-    }
+        for fd in self.removed.drain(..) {
+            poller.delete(fd)?;
 
-    pub(crate) fn fetch_or_set(&self, fd: RawFd, event: Event, waker: &Waker) -> io::Result<bool> {
-        self.modify(|regs| {
-            if regs.fetch(fd, event)? {
-                Ok(true)
+            trace!("Deleted registration FD: {fd}");
+        }
+
+        if let Some(event_fd) = self.event_fd.as_ref().map(|event_fd| event_fd.as_raw_fd()) {
+            if !self.event_fd_polled {
+                poller.add(event_fd, Event::Read.into())?;
+                self.event_fd_polled = true;
+
+                trace!("Added event FD: {event_fd}");
+            }
+        }
+
+        for registration in &mut self.vec {
+            let interest = EnumSet::<Event>::all()
+                .iter()
+                .filter(|event| registration.wakers[*event as usize].is_some())
+                .collect::<EnumSet<_>>()
+                .difference(registration.disarmed);
+
+            match registration.polled {
+                None => poller.add(registration.fd, interest)?,
+                Some(polled) if polled != interest => poller.modify(registration.fd, interest)?,
+                _ => continue,
+            }
+
+            registration.polled = Some(interest);
+
+            trace!("Set registration FD: {}/{interest:?}", registration.fd);
+        }
+
+        let active = !self.vec.is_empty();
+
+        if active != self.active {
+            self.active = active;
+
+            debug!("Reactor is now {}", if active { "active" } else { "idle" });
+
+            if let Some(on_activity_change) = self.config.on_activity_change {
+                on_activity_change(active);
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        {
+            // Idle registrations are not watched, so they do not count
+            self.stats.max_fd = self
+                .vec
+                .iter()
+                .filter(|reg| reg.polled.is_some_and(|polled| !polled.is_empty()))
+                .map(|reg| reg.fd)
+                .chain(self.event_fd.as_ref().map(|event_fd| event_fd.as_raw_fd()))
+                .max();
+        }
+
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    #[allow(deprecated)]
+    fn update_events(&mut self, ready: &[ReadyEvent]) -> io::Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "reactor_pass",
+            registrations = self.vec.len(),
+            woken = tracing::field::Empty
+        )
+        .entered();
+
+        trace!("Updating events");
+
+        self.consume_notification()?;
+
+        let mut woken = 0;
+        let mut capped = false;
+
+        let now = Instant::now();
+
+        // A single walk over the ready events, rather than a walk per registration
+        for ready in ready {
+            if let Ok(index) = self.vec.binary_search_by_key(&ready.fd, |reg| reg.fd) {
+                self.vec[index].ready |= ready.events;
+            }
+        }
+
+        let max_wakes = self.config.max_wakes_per_pass.unwrap_or(usize::MAX);
+        let len = self.vec.len();
+        let start = if len > 0 { self.wake_cursor % len } else { 0 };
+
+        for offset in 0..len {
+            let index = (start + offset) % len;
+            let registration = &mut self.vec[index];
+
+            let ready = core::mem::take(&mut registration.ready);
+
+            for event in EnumSet::ALL {
+                if ready.contains(event) && !registration.disarmed.contains(event) {
+                    #[cfg(feature = "tracing")]
+                    trace!(fd = registration.fd, ?event, "Registration FD is set");
+                    #[cfg(not(feature = "tracing"))]
+                    trace!("Registration FD is set: {}/{event:?}", registration.fd);
+
+                    registration.events |= event;
+
+                    if woken == max_wakes {
+                        // Leave the waker armed for the next pass, and start the next pass from here
+                        if !capped {
+                            trace!("Max wakes per pass reached");
+
+                            self.wake_cursor = index;
+                            capped = true;
+                        }
+
+                        continue;
+                    }
+
+                    if registration.callbacks.contains(event) {
+                        // Callbacks stay armed until the file descriptor is deregistered
+                        if let Some(waker) = registration.wakers[event as usize].as_ref() {
+                            waker.wake_by_ref();
+                            woken += 1;
+
+                            #[cfg(feature = "metrics")]
+                            registration.count_wake(event);
+                        }
+                    } else if let Some(waker) = registration.wakers[event as usize].take() {
+                        registration.deadlines[event as usize] = None;
+                        self.dirty = true;
+
+                        waker.wake();
+                        woken += 1;
+
+                        #[cfg(feature = "metrics")]
+                        registration.count_wake(event);
+                    }
+
+                    if registration.oneshot {
+                        registration.disarmed |= event;
+                        self.dirty = true;
+                    }
+                } else if registration.deadlines[event as usize]
+                    .is_some_and(|deadline| deadline <= now)
+                {
+                    trace!(
+                        "Registration deadline expired: {}/{event:?}",
+                        registration.fd
+                    );
+
+                    registration.deadlines[event as usize] = None;
+
+                    if !registration.callbacks.contains(event) {
+                        if let Some(waker) = registration.wakers[event as usize].take() {
+                            self.dirty = true;
+
+                            waker.wake();
+                            woken += 1;
+
+                            #[cfg(feature = "metrics")]
+                            registration.count_wake(event);
+                        }
+                    }
+                }
+            }
+        }
+
+        // The yielding tasks are woken on each pass, regardless of any file descriptor readiness
+        for waker in self.yield_wakers.drain(..) {
+            waker.wake();
+            woken += 1;
+        }
+
+        if let Some(hook) = self.pass_hook.as_mut() {
+            hook();
+        }
+
+        #[cfg(feature = "tracing")]
+        span.record("woken", woken);
+
+        Ok(woken)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_modify(&mut self, held: Duration) {
+        self.stats.modify_hold_last = held;
+        self.stats.modify_hold_max = self.stats.modify_hold_max.max(held);
+
+        if self
+            .config
+            .slow_modify_threshold
+            .is_some_and(|threshold| held > threshold)
+        {
+            warn!("A registration or interest change held the reactor lock for {held:?}");
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_pass(&mut self, select_end: Instant) {
+        let notified_at = self.notified_at.take();
+
+        self.stats.record_pass(select_end, notified_at);
+    }
+
+    fn fail(&mut self, err: io::Error) {
+        self.failure = Some(err);
+
+        self.dirty = true;
+
+        // Wake everybody, so that they can find out about the failure. The callbacks are dropped rather than called,
+        // as the file descriptors are not ready and the callbacks are only ever called on the reactor thread
+        for registration in &mut self.vec {
+            for event in registration.callbacks {
+                registration.wakers[event as usize] = None;
+            }
+
+            registration.callbacks = EnumSet::empty();
+
+            for waker in &mut registration.wakers {
+                if let Some(waker) = waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+
+        if let Some(waker) = self.health_waker.take() {
+            waker.wake();
+        }
+
+        for waker in self
+            .sync_wakers
+            .drain(..)
+            .chain(self.yield_wakers.drain(..))
+        {
+            waker.wake();
+        }
+    }
+
+    /// Marks the completion of a pass, i.e. the file descriptor sets for the next `select` call
+    /// incorporate all registration changes done so far.
+    fn complete_pass(&mut self) {
+        self.passes += 1;
+
+        for waker in self.sync_wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
+    fn failure(&self) -> Option<io::Error> {
+        self.failure
+            .as_ref()
+            .map(|err| io::Error::new(err.kind(), err.to_string()))
+    }
+
+    fn create_notification(&mut self) -> io::Result<bool> {
+        if self.event_fd.is_none() {
+            #[cfg(any(feature = "notify-pipe", target_vendor = "apple"))]
+            let event_fd = {
+                let (read_fd, write_fd) = notification_pipe()?;
+
+                self.notify_fd = write_fd;
+
+                read_fd
+            };
+
+            #[cfg(all(
+                not(any(feature = "notify-pipe", target_vendor = "apple")),
+                not(target_os = "espidf")
+            ))]
+            let event_fd = unsafe {
+                OwnedFd::from_raw_fd(syscall_los!(sys::eventfd(
+                    self.config.eventfd_initval,
+                    self.config.eventfd_flags | sys::EFD_NONBLOCK
+                ))?)
+            };
+
+            // Note that the eventfd() implementation in ESP-IDF deviates from the specification in the following ways:
+            // 1) The file descriptor is always in a non-blocking mode, as if EFD_NONBLOCK was passed as a flag;
+            //    passing EFD_NONBLOCK or calling fcntl(.., F_GETFL/F_SETFL) on the eventfd() file descriptor is not supported
+            // 2) It always returns the counter value, even if it is 0. This is contrary to the specification which mandates
+            //    that it should instead fail with EAGAIN
+            //
+            // (1) is not a problem for us, as we want the eventfd() file descriptor to be in a non-blocking mode anyway
+            // (2) is also not a problem, as long as we don't try to read the counter value in an endless loop when we detect being notified
+            #[cfg(all(not(feature = "notify-pipe"), target_os = "espidf"))]
+            let event_fd = unsafe {
+                let (initval, flags) = (self.config.eventfd_initval, self.config.eventfd_flags);
+
+                OwnedFd::from_raw_fd(syscall_los!(sys::eventfd(initval as _, flags as _)).map_err(|err| {
+                    match err {
+                        err if err.kind() == io::ErrorKind::PermissionDenied => {
+                            // EPERM can happen if the eventfd isn't initialized yet.
+                            // Tell the user to call esp_vfs_eventfd_register.
+                            io::Error::new(
+                                io::ErrorKind::PermissionDenied,
+                                "failed to initialize eventfd for polling, try calling `esp_vfs_eventfd_register`"
+                            )
+                        },
+                        err => err,
+                    }
+                })?)
+            };
+
+            debug!("Created event FD: {}", event_fd.as_raw_fd());
+
+            self.event_fd = Some(event_fd);
+            self.dirty = true;
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn destroy_notification<P: Poller>(&mut self, poller: &mut P) -> io::Result<bool> {
+        if let Some(event_fd) = self.event_fd.take() {
+            self.dirty = true;
+
+            if self.event_fd_polled {
+                self.event_fd_polled = false;
+
+                poller.delete(event_fd.as_raw_fd())?;
+            }
+
+            #[cfg(any(feature = "notify-pipe", target_vendor = "apple"))]
+            drop(self.notify_fd.take());
+
+            let event_fd = event_fd.into_raw_fd();
+
+            // Closed explicitly rather than by dropping, so that a failure to close is reported
+            syscall_los!(unsafe { sys::close(event_fd) })?;
+
+            debug!("Closed event FD: {event_fd}");
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn notify(&mut self) -> io::Result<bool> {
+        #[cfg(any(feature = "notify-pipe", target_vendor = "apple"))]
+        let event_fd = self.notify_fd.as_ref().or(self.event_fd.as_ref());
+        #[cfg(not(any(feature = "notify-pipe", target_vendor = "apple")))]
+        let event_fd = self.event_fd.as_ref();
+
+        if let Some(event_fd) = event_fd {
+            let event_fd = event_fd.as_raw_fd();
+
+            #[cfg(feature = "metrics")]
+            if self.notified_at.is_none() {
+                self.notified_at = Some(Instant::now());
+            }
+
+            syscall_los_eagain!(unsafe {
+                sys::write(
+                    event_fd,
+                    // The eventfd counter is a native-endian integer, which matters in semaphore mode, where
+                    // each pass decrements it by one
+                    &u64::to_ne_bytes(1_u64) as *const _ as *const _,
+                    core::mem::size_of::<u64>(),
+                )
+            })?;
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn consume_notification(&mut self) -> io::Result<bool> {
+        if let Some(event_fd) = self.event_fd.as_ref() {
+            let event_fd = event_fd.as_raw_fd();
+
+            let mut buf = [0_u8; core::mem::size_of::<u64>()];
+
+            // In semaphore mode, this consumes a single notification, and the eventfd stays readable if there are
+            // more, so the next pass consumes the next one
+            #[cfg(not(any(feature = "notify-pipe", target_vendor = "apple")))]
+            syscall_los_eagain!(unsafe {
+                sys::read(
+                    event_fd,
+                    &mut buf as *mut _ as *mut _,
+                    core::mem::size_of::<u64>(),
+                )
+            })?;
+
+            // Unlike an eventfd counter, the pipe accumulates the notifications, so drain it
+            #[cfg(any(feature = "notify-pipe", target_vendor = "apple"))]
+            loop {
+                match syscall_los!(unsafe {
+                    sys::read(event_fd, &mut buf as *mut _ as *mut _, buf.len())
+                }) {
+                    Ok(len) if len > 0 => continue,
+                    Ok(_) => break,
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                    Err(err) => Err(err)?,
+                }
+            }
+
+            trace!("Consumed notification");
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+/// A reactor serving the [`Async`](crate::Async) I/O handles (see [`REACTOR`]).
+///
+/// The reactor waits for I/O events with a [`Poller`], which is the `select`-based [`SelectPoller`] by default.
+/// The global [`REACTOR`] uses the `poll`-based [`PollPoller`](crate::PollPoller) instead when the `poll` feature
+/// is enabled.
+///
+/// # Pass ordering
+///
+/// Each reactor pass runs the following steps under the registrations lock, and then waits on the poller
+/// (outside of the lock):
+///
+/// 1. The notification (see [`Reactor::notify`]) is consumed, if the previous wait was woken by it.
+/// 2. The wakers of the file descriptors found ready by the previous wait are woken, and the expired
+///    deadlines are handled.
+/// 3. The poller interest is synced with the current registrations and armed wakers.
+/// 4. The pending registration and interest changes are acknowledged.
+///
+/// The lock is released between steps 2 and 3, so that the woken tasks can submit their changes right away.
+/// Step 3 includes every change submitted before it, even those whose notification woke the previous wait
+/// together with other ready file descriptors. So a change never waits for an extra pass, and a change submitted
+/// after step 3 is picked up by the next wait, as its notification is still pending.
+pub struct Reactor<const N: usize, P = SelectPoller<N>> {
+    registrations: std::sync::Mutex<Registrations<N>>,
+    /// Only locked by the thread driving the reactor
+    poller: std::sync::Mutex<P>,
+    condvar: std::sync::Condvar,
+    started: AtomicBool,
+    inline: AtomicBool,
+}
+
+impl<const N: usize, P: Poller + Send> Reactor<N, P> {
+    /// Creates a reactor which waits for I/O events with the provided poller.
+    ///
+    /// Note that [`Async`](crate::Async) always uses the [`REACTOR`] (whose poller is selected with
+    /// the `poll` feature), so this is only useful for integrating raw file descriptors with
+    /// a custom poller, or for driving a poller other than the [`REACTOR`] one.
+    pub const fn with_poller(poller: P) -> Self {
+        const { assert_fd_limit::<P>(N) };
+
+        Self {
+            registrations: std::sync::Mutex::new(Registrations::new(P::FD_LIMIT)),
+            poller: std::sync::Mutex::new(poller),
+            condvar: std::sync::Condvar::new(),
+            started: AtomicBool::new(false),
+            inline: AtomicBool::new(false),
+        }
+    }
+
+    /// Starts the reactor. Returns `false` if it had been already started.
+    pub fn start(&'static self) -> io::Result<bool> {
+        self.start_with_config(ReactorConfig::new())
+    }
+
+    /// Starts the reactor with the provided configuration. Returns `false` if it had been already started,
+    /// in which case the configuration is ignored.
+    ///
+    /// Note that the reactor is started automatically with the default configuration when the first
+    /// [`Async`](crate::Async) is created, so this method needs to be called before that.
+    pub fn start_with_config(&'static self, config: ReactorConfig) -> io::Result<bool> {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return Ok(false);
+        }
+
+        info!("Starting reactor");
+
+        self.lock(|mut guard| {
+            guard.config = config;
+
+            Ok(())
+        })?;
+
+        // Note that registrations may race with the reactor thread creating its notification file descriptor.
+        // This is fine, because such registrations do not need a notification: they wait on the condition variable
+        // until acknowledged, and the reactor thread acknowledges all pending modifications - and picks up
+        // their file descriptors - in its very first pass, which happens before it enters `select` for the first time.
+        std::thread::Builder::new()
+            .name("async-io-mini".into())
+            .stack_size(3048)
+            .spawn(move || {
+                let _ = self.serve();
+            })?;
+
+        Ok(true)
+    }
+
+    /// Starts the reactor - unless it had been already started - and blocks the calling thread until the reactor
+    /// has completed its first pass, i.e. until it has created its notification file descriptor and is about to
+    /// wait for I/O events for the first time.
+    ///
+    /// Registrations done right after [`Reactor::start`] are correct, but they wait for the reactor thread to
+    /// come up. This method moves that wait to a well-defined point (e.g. the startup of the application),
+    /// and makes the timing of the subsequent operations deterministic.
+    ///
+    /// Returns the error of the reactor, if it fails before completing its first pass.
+    pub fn start_and_wait(&'static self) -> io::Result<()> {
+        self.start()?;
+
+        let guard = self
+            .condvar
+            .wait_while(self.registrations.lock().unwrap(), |registrations| {
+                registrations.passes == 0 && registrations.failure.is_none()
+            })
+            .unwrap();
+
+        if let Some(err) = guard.failure() {
+            Err(err)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the reactor on the calling thread, rather than on a thread spawned by the reactor.
+    ///
+    /// This allows RTOS integrations to create the reactor thread (task) themselves, e.g. with a platform-specific
+    /// priority or stack placement. The method returns only if the reactor fails, with the error the reactor failed with.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::AlreadyExists`] if the reactor had been already started.
+    /// Note that the reactor is started automatically when the first [`Async`](crate::Async) is created,
+    /// so this method needs to be called before that. Registrations done before the reactor enters its first
+    /// pass wait for it, as they do with [`Reactor::start`].
+    pub fn run_blocking(&'static self) -> io::Result<()> {
+        self.run_blocking_with_config(ReactorConfig::new())
+    }
+
+    /// Runs the reactor on the calling thread with the provided configuration.
+    ///
+    /// The semantics are the same as those of [`Reactor::run_blocking`].
+    pub fn run_blocking_with_config(&'static self, config: ReactorConfig) -> io::Result<()> {
+        if self.started.swap(true, Ordering::SeqCst) {
+            Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                "the reactor is already started",
+            ))?;
+        }
+
+        info!("Running reactor on the calling thread");
+
+        self.lock(|mut guard| {
+            guard.config = config;
+
+            Ok(())
+        })?;
+
+        self.serve()
+    }
+
+    /// Registers a file descriptor in the reactor.
+    ///
+    /// The reactor must be started first with [`Reactor::start`] or [`Reactor::start_with_config`],
+    /// or else an error is returned, as nobody would be servicing the registration.
+    ///
+    /// Note that [`Async`](crate::Async) takes care of registering (and starting the reactor)
+    /// automatically, so this method is only useful for integrating raw file descriptors.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::AlreadyExists`] if the file descriptor is already registered.
+    /// If however the file descriptor number is still registered, but now refers to a different file object
+    /// (i.e. the previous file descriptor was closed without being deregistered and the OS recycled its number),
+    /// the stale registration is reclaimed - with a warning - and its wakers are dropped without being woken.
+    pub fn register(&self, fd: RawFd) -> io::Result<()> {
+        self.modify(|regs| regs.register(fd, false))
+    }
+
+    /// Registers a file descriptor in the reactor, together with an opaque user data token.
+    ///
+    /// The token (e.g. a slab index of a connection table) can be retrieved with [`Reactor::user_data`],
+    /// similarly to the `epoll_data` of epoll. This spares integrators a separate lookup table mapping file
+    /// descriptors to their own bookkeeping. File descriptors registered with [`Reactor::register`] have
+    /// a token of 0.
+    ///
+    /// Otherwise the same as [`Reactor::register`].
+    pub fn register_with_data(&self, fd: RawFd, data: usize) -> io::Result<()> {
+        self.modify(|regs| {
+            regs.register(fd, false)?;
+            regs.set_data(fd, data)
+        })
+    }
+
+    /// Returns the user data token of the registered file descriptor (see [`Reactor::register_with_data`]).
+    pub fn user_data(&self, fd: RawFd) -> io::Result<usize> {
+        self.lock(|guard| guard.data(fd))
+    }
+
+    /// Registers a file descriptor in the reactor, which is known to be already ready for the `initial` events
+    /// (e.g. a pipe with pending data, or a UART with buffered input).
+    ///
+    /// The first wait for any of these events completes right away, without a `select` round-trip. Make sure
+    /// that the file descriptor really is ready, as a spurious readiness merely costs a `WouldBlock` and a retry
+    /// with operations like [`Async::read_with`](crate::Async::read_with), but might confuse code relying on it.
+    ///
+    /// Otherwise the same as [`Reactor::register`].
+    pub fn register_with_readiness(&self, fd: RawFd, initial: EnumSet<Event>) -> io::Result<()> {
+        self.modify(|regs| {
+            regs.register(fd, false)?;
+            regs.set_readiness(fd, initial)
+        })
+    }
+
+    /// Registers a file descriptor in the reactor and arms the waker for the provided event, in a single
+    /// reactor round-trip.
+    ///
+    /// This is the same as [`Reactor::register`] followed by a wait for the event, but without the second
+    /// round-trip, which matters for short-lived, one-shot operations (e.g. connect once, read once, close),
+    /// where each round-trip is a context switch to the reactor thread.
+    ///
+    /// Returns `true` if the file descriptor is already known to be ready for the event, in which case
+    /// the waker is not armed. Otherwise, the waker is woken once the reactor observes the readiness.
+    ///
+    /// The file descriptor must be deregistered with [`Reactor::deregister`] before it is closed, as usual.
+    pub fn register_and_wait(&self, fd: RawFd, event: Event, waker: &Waker) -> io::Result<bool> {
+        self.modify(|regs| {
+            regs.register(fd, false)?;
+
+            if regs.fetch(fd, event)? {
+                Ok(true)
+            } else {
+                regs.set(fd, event, waker)?;
+
+                Ok(false)
+            }
+        })
+    }
+
+    /// Registers a file descriptor in the reactor, with each of its events disarmed once reported
+    /// (similarly to `EPOLLONESHOT`).
+    ///
+    /// Once the reactor has reported the readiness of the file descriptor for an event (i.e. has woken the waker
+    /// or called the callback of the event), it stops watching the file descriptor for that event, until the event
+    /// is re-armed with [`Reactor::set_interest`]. This is mostly useful with callbacks (see
+    /// [`Reactor::register_callback`]), which otherwise stay armed: e.g. a readable file descriptor handed over
+    /// to a pool of workers is then reported once, rather than on each reactor pass until a worker drains it.
+    ///
+    /// Note that waits (e.g. [`Async::readable`](crate::Async::readable)) for a disarmed event only complete
+    /// on their deadline, if any.
+    ///
+    /// Otherwise the same as [`Reactor::register`].
+    pub fn register_oneshot(&self, fd: RawFd) -> io::Result<()> {
+        self.modify(|regs| {
+            regs.register(fd, false)?;
+            regs.set_oneshot(fd)
+        })
+    }
+
+    /// Sets the events the reactor watches the registered file descriptor for, disarming all other events.
+    ///
+    /// This re-arms the events of a registration done with [`Reactor::register_oneshot`], once they were reported.
+    /// All events are armed for new registrations.
+    pub fn set_interest(&self, fd: RawFd, events: EnumSet<Event>) -> io::Result<()> {
+        self.modify(|regs| regs.set_interest(fd, events))
+    }
+
+    /// Sets aside `n` registration slots, which [`Reactor::register`] does not consume and which are only
+    /// available to [`Reactor::register_reserved`].
+    ///
+    /// This guarantees that critical file descriptors (e.g. a control socket or the listener itself) can always
+    /// be registered, even when the other registrations (e.g. accepted connections) have exhausted the rest
+    /// of the slots.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if `n` exceeds the reactor capacity, and
+    /// of kind [`io::ErrorKind::OutOfMemory`] if the current registrations leave less than `n` slots free.
+    pub fn reserve(&self, n: usize) -> io::Result<()> {
+        self.lock(|mut guard| guard.reserve(n))
+    }
+
+    /// Registers a file descriptor in the reactor, using one of the slots set aside with [`Reactor::reserve`]
+    /// if no other slot is free.
+    ///
+    /// Otherwise the same as [`Reactor::register`].
+    pub fn register_reserved(&self, fd: RawFd) -> io::Result<()> {
+        self.modify(|regs| regs.register(fd, true))
+    }
+
+    /// Deregisters a file descriptor from the reactor.
+    ///
+    /// The file descriptor must be deregistered before it is closed.
+    pub fn deregister(&self, fd: RawFd) -> io::Result<()> {
+        self.modify(|regs| regs.deregister(fd))
+    }
+
+    /// Registers a callback to be called whenever the (already registered) file descriptor is ready
+    /// for the provided event.
+    ///
+    /// This allows non-async code to use the reactor for readiness notifications. The callback stays
+    /// armed until the file descriptor is deregistered, so it is called on each reactor pass where the file
+    /// descriptor is ready. Hence, the callback should consume the readiness (i.e. drain the readable data)
+    /// or else it would be called over and over again.
+    ///
+    /// While the callback is registered, waiting for the same event on the file descriptor (e.g. with
+    /// [`Async::readable`](crate::Async::readable)) fails with [`ErrorKind::AlreadyExists`]. If the reactor fails,
+    /// the callback is dropped without being called.
+    ///
+    /// # Reentrancy
+    ///
+    /// The callback is called on the reactor thread, while the reactor is holding its internal lock. Therefore,
+    /// the callback must not block, and must not call back into the reactor (i.e. [`Reactor::register`]
+    /// or [`Reactor::deregister`]), as that would deadlock the reactor.
+    pub fn register_callback(
+        &self,
+        fd: RawFd,
+        event: Event,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> io::Result<()> {
+        let callback = Waker::from(std::sync::Arc::new(Callback(std::sync::Mutex::new(
+            callback,
+        ))));
+
+        self.modify(|regs| regs.set_callback(fd, event, callback))
+    }
+
+    /// Wakes up the reactor, causing it to run a new pass.
+    ///
+    /// Together with [`Reactor::set_pass_hook`], this allows bridging event sources which are not
+    /// file descriptors (e.g. a FreeRTOS queue) into the reactor: the producer calls this method,
+    /// and the hook checks the event source on the reactor thread.
+    ///
+    /// Does nothing if the reactor is not running.
+    pub fn notify(&self) -> io::Result<()> {
+        self.lock(|mut guard| guard.notify().map(|_| ()))
+    }
+
+    /// Returns a future which resolves once the reactor has started a pass which incorporates all
+    /// registration changes done before this call.
+    ///
+    /// This is a barrier for code which needs to be sure that a registration is "live" - i.e. that the reactor
+    /// is actually watching the file descriptor - before relying on it, e.g. in deterministic test setups.
+    ///
+    /// Fails if the reactor is not started or has failed.
+    pub fn sync(&self) -> impl Future<Output = io::Result<()>> + '_ {
+        let mut target = None;
+
+        poll_fn(move |cx| {
+            if !self.started.load(Ordering::SeqCst) {
+                Err(io::Error::other(
+                    "the reactor is not started; call `Reactor::start` first",
+                ))?;
+            }
+
+            self.lock(|mut guard| {
+                if let Some(err) = guard.failure() {
+                    Err(err)?;
+                }
+
+                let target = *target.get_or_insert(guard.passes + 1);
+
+                if guard.passes >= target {
+                    return Ok(Poll::Ready(()));
+                }
+
+                if !guard
+                    .sync_wakers
+                    .iter()
+                    .any(|waker| waker.will_wake(cx.waker()))
+                {
+                    guard.sync_wakers.push(cx.waker().clone());
+                }
+
+                guard.notify()?;
+
+                Ok(Poll::Pending)
+            })?
+            .map(Ok)
+        })
+    }
+
+    /// Yields to the executor: the returned future is woken on the next reactor pass and resolves when polled
+    /// again, regardless of any file descriptor readiness.
+    ///
+    /// This is useful for breaking up long CPU-bound tasks, so that other tasks get a chance to run. The next pass
+    /// is triggered via the reactor notification, so there is no busy spinning.
+    ///
+    /// Fails if the reactor is not started or has failed.
+    pub fn yield_now(&self) -> impl Future<Output = io::Result<()>> + '_ {
+        let mut yielded = false;
+
+        poll_fn(move |cx| {
+            if yielded {
+                return Poll::Ready(Ok(()));
+            }
+
+            if !self.started.load(Ordering::SeqCst) {
+                Err(io::Error::other(
+                    "the reactor is not started; call `Reactor::start` first",
+                ))?;
+            }
+
+            self.lock(|mut guard| {
+                if let Some(err) = guard.failure() {
+                    Err(err)?;
+                }
+
+                guard.yield_wakers.push(cx.waker().clone());
+                guard.notify()?;
+
+                Ok(())
+            })?;
+
+            yielded = true;
+
+            Poll::Pending
+        })
+    }
+
+    /// Wakes `waker` from the reactor thread on its next pass, or directly if the reactor is not running.
+    pub(crate) fn wake(&self, waker: Waker) {
+        if self.started.load(Ordering::SeqCst) {
+            let waker = self
+                .lock(|mut guard| {
+                    if guard.failure.is_some() {
+                        return Ok(Some(waker));
+                    }
+
+                    guard.yield_wakers.push(waker);
+
+                    // Keep the waker queued even if the notification fails; the reactor is about to fail then
+                    let _ = guard.notify();
+
+                    Ok(None)
+                })
+                .unwrap();
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        } else {
+            waker.wake();
+        }
+    }
+
+    /// A blocking variant of [`Reactor::sync`].
+    ///
+    /// This is the same round-trip which all registration changes (e.g. [`Reactor::register`]) do implicitly.
+    ///
+    /// In inline mode (see [`Reactor::poll_once`]), this method returns immediately, as the reactor is likely
+    /// driven by the calling thread and waiting for it would deadlock. Use [`Reactor::sync`] instead.
+    pub fn sync_blocking(&self) -> io::Result<()> {
+        self.modify(|_| Ok(()))
+    }
+
+    /// Wakes all wakers currently stored in the reactor, without disarming them.
+    ///
+    /// This is useful when shutting down an executor: all tasks waiting for I/O get re-polled, so they can observe
+    /// their cancellation and exit cleanly, rather than waiting for an unrelated I/O event. The callbacks registered
+    /// with [`Reactor::register_callback`] are not called, as they are not tasks.
+    pub fn wake_all(&self) -> io::Result<()> {
+        self.lock(|regs| {
+            for registration in &regs.vec {
+                for event in EnumSet::<Event>::all() {
+                    if !registration.callbacks.contains(event) {
+                        if let Some(waker) = registration.wakers[event as usize].as_ref() {
+                            waker.wake_by_ref();
+                        }
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Sets (or with `None`, removes) a hook which is called on each reactor pass, after the wakers
+    /// of the ready file descriptors have been woken.
+    ///
+    /// The hook is called on the reactor thread, with the same reentrancy restrictions as
+    /// the callbacks of [`Reactor::register_callback`]. Use [`Reactor::notify`] to force a pass.
+    pub fn set_pass_hook(&self, hook: Option<Box<dyn FnMut() + Send>>) -> io::Result<()> {
+        self.lock(|mut guard| {
+            guard.pass_hook = hook;
+
+            Ok(())
+        })
+    }
+
+    // pub(crate) fn set(&self, fd: RawFd, event: Event, waker: &Waker) -> io::Result<()> {
+    //     self.lock(|regs| regs.set(fd, event, waker))
+    // }
+
+    /// Atomically - in a single reactor round-trip - stops watching the file descriptor for the `from` event,
+    /// and starts watching it for the `to` event, waking `waker` once the file descriptor is ready for `to`.
+    ///
+    /// Returns `true` if the reactor had already observed the file descriptor being ready for `to`, in which
+    /// case the readiness is consumed and the waker is not armed.
+    ///
+    /// This is useful for state machines (e.g. TLS handshakes) which alternate between needing read and write
+    /// readiness of the underlying transport.
+    pub fn swap_interest(
+        &self,
+        fd: RawFd,
+        from: Event,
+        to: Event,
+        waker: &Waker,
+    ) -> io::Result<bool> {
+        self.modify(|regs| {
+            regs.clear(fd, EnumSet::only(from))?;
+
+            if regs.fetch(fd, to)? {
+                Ok(true)
+            } else {
+                regs.set(fd, to, waker)?;
+
+                Ok(false)
+            }
+        })
+    }
+
+    /// Applies several "fetch the readiness, or else arm the waker" operations in a single reactor round-trip,
+    /// returning for each of them whether the file descriptor was already ready for the event (in which case
+    /// the readiness is consumed and the waker is not armed).
+    ///
+    /// This is for futures waiting on several file descriptors at once (e.g. a `select`-style multiplexer),
+    /// which would otherwise need one round-trip per file descriptor. No round-trip is needed at all if all
+    /// of the file descriptors are already known to be ready. Note that [`ready_any`](crate::ready_any) batches
+    /// its operations already.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if there are more than `M` operations. If an
+    /// operation fails (e.g. as its file descriptor is not registered), the preceding ones remain applied.
+    pub fn modify_batch<const M: usize>(
+        &self,
+        ops: &[InterestOp<'_>],
+    ) -> io::Result<heapless::Vec<bool, M>> {
+        if ops.len() > M {
+            Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "more operations than the capacity of the result",
+            ))?;
+        }
+
+        // Fast path: consume the readiness which is already known, without a reactor round-trip
+        let mut ready = self.lock(|mut regs| {
+            ops.iter()
+                .map(|op| regs.fetch(op.fd, op.event))
+                .collect::<io::Result<heapless::Vec<bool, M>>>()
+        })?;
+
+        if ready.iter().all(|ready| *ready) {
+            return Ok(ready);
+        }
+
+        self.modify(|regs| {
+            for (op, ready) in ops.iter().zip(ready.iter_mut()) {
+                if !*ready {
+                    *ready = regs.fetch(op.fd, op.event)?;
+
+                    if !*ready {
+                        regs.set(op.fd, op.event, op.waker)?;
+                    }
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(ready)
+    }
+
+    /// Consumes the readiness of all registered file descriptors at once, appending the file descriptors which
+    /// were ready to `out`, together with the events they were ready for.
+    ///
+    /// This is the batch counterpart of fetching the readiness of each file descriptor on its own (e.g. with
+    /// [`Reactor::register_and_wait`]), for integrators which want to snapshot the whole ready set after a reactor
+    /// pass (e.g. a custom scheduler processing all ready I/O before making a scheduling decision). All of it
+    /// happens under a single lock, without a reactor round-trip.
+    ///
+    /// Returns the number of file descriptors appended to `out`. Once `out` is full, the readiness of the remaining
+    /// file descriptors is left in place, to be consumed by a subsequent call.
+    pub fn drain_ready<const M: usize>(
+        &self,
+        out: &mut heapless::Vec<(RawFd, EnumSet<Event>), M>,
+    ) -> io::Result<usize> {
+        self.lock(|mut regs| {
+            let mut count = 0;
+
+            for registration in regs.vec.iter_mut() {
+                if registration.events.is_empty() {
+                    continue;
+                }
+
+                if out.push((registration.fd, registration.events)).is_err() {
+                    break;
+                }
+
+                registration.events.clear();
+                count += 1;
+            }
+
+            Ok(count)
+        })
+    }
+
+    pub(crate) fn clear(&self, fd: RawFd, events: EnumSet<Event>) -> io::Result<()> {
+        self.modify(|regs| regs.clear(fd, events))
+    }
+
+    pub(crate) fn fetch(&self, fd: RawFd, event: Event) -> io::Result<bool> {
+        // Consuming the readiness does not change the file descriptors the reactor is watching,
+        // so there is no need for a (costly) reactor round-trip
+        self.lock(|mut regs| regs.fetch(fd, event))
+    }
+
+    /// Returns whether the file descriptor is known to be ready for the event, without consuming the readiness.
+    pub(crate) fn is_ready(&self, fd: RawFd, event: Event) -> io::Result<bool> {
+        self.lock(|regs| {
+            regs.vec
+                .iter()
+                .find(|reg| reg.fd == fd)
+                .map(|reg| reg.events.contains(event))
+                .ok_or_else(|| ErrorKind::NotFound.into())
+        })
+    }
+
+    pub(crate) fn fetch_or_set(&self, fd: RawFd, event: Event, waker: &Waker) -> io::Result<bool> {
+        // Fast path: the readiness is already known, so no reactor round-trip is necessary
+        if self.fetch(fd, event)? {
+            return Ok(true);
+        }
+
+        self.modify(|regs| {
+            if regs.fetch(fd, event)? {
+                Ok(true)
             } else {
                 regs.set(fd, event, waker)?;
 
@@ -370,76 +1780,463 @@ impl<const N: usize> Reactor<N> {
         })
     }
 
+    /// Waits until the file descriptor is ready for any of the events in `interest`, or until the
+    /// optional deadline passes.
+    ///
+    /// Resolves with the events the file descriptor is ready for, or with an error of kind
+    /// [`io::ErrorKind::TimedOut`] if the deadline passes first. The deadline is enforced by the reactor
+    /// itself (by bounding the timeout of its `select` call), so no separate timer is necessary.
+    ///
+    /// The readiness methods of [`Async`](crate::Async) are built on top of this method.
+    ///
+    /// If the future is dropped before it resolves, its deadline is removed from the reactor, so that abandoned
+    /// waits (e.g. a timed out read raced against another future) do not wake the reactor needlessly.
+    pub fn ready(
+        &self,
+        fd: RawFd,
+        interest: EnumSet<Event>,
+        deadline: Option<Instant>,
+    ) -> impl Future<Output = io::Result<EnumSet<Event>>> + '_ {
+        let mut guard = DeadlineGuard {
+            reactor: self,
+            fd,
+            interest,
+            deadline: None,
+        };
+
+        poll_fn(move |cx| {
+            // Moves the whole guard into the closure, so that it is dropped together with the future
+            let guard = &mut guard;
+
+            // Fast path: the readiness is already known, so no reactor round-trip is necessary
+            let ready = self.lock(|mut regs| {
+                let ready = regs.fetch_multi(fd, interest)?;
+
+                if !ready.is_empty() {
+                    regs.set_deadline(fd, interest, None)?;
+                }
+
+                Ok(ready)
+            })?;
+
+            if !ready.is_empty() {
+                guard.deadline = None;
+
+                return Poll::Ready(Ok(ready));
+            }
+
+            let expired = deadline.is_some_and(|deadline| deadline <= Instant::now());
+
+            let ready = self.modify(|regs| {
+                let ready = regs.fetch_multi(fd, interest)?;
+
+                if ready.is_empty() && !expired {
+                    regs.set_multi(fd, interest, cx.waker())?;
+                    regs.set_deadline(fd, interest, deadline)?;
+                } else {
+                    regs.set_deadline(fd, interest, None)?;
+                }
+
+                Ok(ready)
+            })?;
+
+            guard.deadline = if ready.is_empty() && !expired {
+                deadline
+            } else {
+                None
+            };
+
+            if !ready.is_empty() {
+                Poll::Ready(Ok(ready))
+            } else if expired {
+                Poll::Ready(Err(ErrorKind::TimedOut.into()))
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+
+    pub(crate) fn fetch_or_set_any<I>(
+        &self,
+        sources: I,
+        waker: &Waker,
+    ) -> io::Result<Option<(usize, EnumSet<Event>)>>
+    where
+        I: IntoIterator<Item = (RawFd, EnumSet<Event>)>,
+    {
+        self.modify(|regs| {
+            for (index, (fd, events)) in sources.into_iter().enumerate() {
+                let ready = regs.fetch_multi(fd, events)?;
+
+                let pending = events.difference(ready);
+                if !pending.is_empty() {
+                    regs.set_multi(fd, pending, waker)?;
+                }
+
+                if !ready.is_empty() {
+                    return Ok(Some((index, ready)));
+                }
+            }
+
+            Ok(None)
+        })
+    }
+
+    /// Runs a single reactor pass on the calling thread, rather than on a dedicated reactor thread.
+    ///
+    /// The pass waits (up to `timeout`, or indefinitely if `None`) for at least one registered file descriptor
+    /// to become ready or for the reactor to be notified, and then wakes the wakers of the ready file descriptors.
+    ///
+    /// Returns the number of wakers (and callbacks) which were woken in this pass. Zero means that
+    /// the pass was woken by a notification, by a timeout, or spuriously; non-zero means that actual
+    /// work was dispatched. A custom executor can use this to decide whether to keep turning the reactor
+    /// or to park.
+    ///
+    /// The first call switches the reactor into inline mode, where no reactor thread is spawned.
+    /// Since creating an [`Async`](crate::Async) starts the reactor thread if the reactor is not started yet,
+    /// the first call to this method needs to happen before that, or else an error is returned.
+    ///
+    /// In inline mode, registration changes do not wait for the reactor to acknowledge them, as the reactor
+    /// is likely driven by the same thread which does the changes.
+    pub fn poll_once(&self, timeout: Option<Duration>) -> io::Result<usize> {
+        self.enter_inline()?;
+
+        let mut poller = self.poller.lock().unwrap();
+
+        let timeout = self.apply(|inner| {
+            inner.create_notification()?;
+            inner.sync_poller(&mut *poller)?;
+            inner.complete_pass();
+
+            Ok(inner.next_timeout(timeout))
+        })?;
+
+        let ready = poller.wait(timeout)?;
+
+        #[cfg(feature = "metrics")]
+        let select_end = Instant::now();
+
+        self.apply(|inner| {
+            let woken = inner.update_events(ready)?;
+
+            #[cfg(feature = "metrics")]
+            inner.record_pass(select_end);
+
+            Ok(woken)
+        })
+    }
+
+    /// Returns the notification file descriptor of the reactor (the eventfd, or the read end of the self-pipe),
+    /// for integrating the reactor with an external event loop (e.g. a C `select` loop, or a GUI loop).
+    ///
+    /// The file descriptor becomes readable whenever the reactor needs a pass to pick up changes, i.e. on
+    /// registration and interest changes (including a task starting to wait for an event), and on
+    /// [`Reactor::notify`]. The readiness is consumed by [`Reactor::poll_once`]. The external loop must not read
+    /// from the file descriptor, nor close it.
+    ///
+    /// Note that the readiness of the registered file descriptors themselves is not reflected by the notification
+    /// file descriptor, so the external loop should watch those as well (it knows them, as it registered them).
+    /// The integration pattern is then:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use async_io_mini::REACTOR;
+    ///
+    /// # fn select(fds: &[i32], timeout: Option<Duration>) {}
+    /// # let socket_fds: Vec<i32> = Vec::new();
+    /// let reactor_fd = REACTOR.notification_fd()?;
+    ///
+    /// loop {
+    ///     // The deadlines of the pending waits bound the external wait
+    ///     let timeout = REACTOR
+    ///         .next_deadline()
+    ///         .map(|deadline| deadline.saturating_duration_since(std::time::Instant::now()));
+    ///
+    ///     select(&[&[reactor_fd], socket_fds.as_slice()].concat(), timeout);
+    ///
+    ///     // Something is ready (or timed out), so run a non-blocking pass
+    ///     REACTOR.poll_once(Some(Duration::ZERO))?;
+    /// }
+    /// # std::io::Result::Ok(())
+    /// ```
+    ///
+    /// As with [`Reactor::poll_once`], the first call switches the reactor into inline mode, and fails if
+    /// the reactor is already running on its own thread.
+    pub fn notification_fd(&self) -> io::Result<RawFd> {
+        self.enter_inline()?;
+
+        self.lock(|mut guard| {
+            guard.create_notification()?;
+
+            Ok(guard.event_fd.as_ref().unwrap().as_raw_fd())
+        })
+    }
+
+    /// Returns a future which resolves with the terminal error of the reactor, if and when the reactor
+    /// thread exits abnormally.
+    ///
+    /// Once the reactor has failed, all pending I/O operations are woken up and fail with the same error,
+    /// as do all subsequent ones. A supervisor task can await this future to find out about the failure
+    /// proactively, and e.g. restart the device.
+    ///
+    /// Only one task at a time should watch the reactor health, as only the most recently registered
+    /// waker is woken up on failure.
+    pub fn watch_health(&self) -> impl Future<Output = io::Error> + '_ {
+        poll_fn(move |cx| {
+            let mut guard = self.registrations.lock().unwrap();
+
+            if let Some(err) = guard.failure() {
+                Poll::Ready(err)
+            } else {
+                guard.health_waker = Some(cx.waker().clone());
+
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Returns the earliest deadline armed in the reactor (see [`Reactor::ready`]), or `None` if only waits
+    /// without a deadline are pending.
+    ///
+    /// An external scheduler (e.g. a FreeRTOS-based main loop driving the reactor with [`Reactor::poll_once`])
+    /// can use this to compute how long it can block without delaying a timeout.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.registrations.lock().unwrap().next_deadline()
+    }
+
+    /// Returns the number of registration and interest changes currently waiting for the reactor to acknowledge
+    /// them.
+    ///
+    /// Each change (e.g. [`Reactor::register`]) is acknowledged on the next reactor pass, so a count which stays
+    /// above 0 for long (e.g. as observed by a watchdog task) indicates that the reactor is not making progress.
+    pub fn waiting_count(&self) -> usize {
+        self.registrations.lock().unwrap().waiting
+    }
+
+    /// Returns a snapshot of the statistics collected by the reactor so far.
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> ReactorStats {
+        self.registrations.lock().unwrap().stats
+    }
+
+    /// Returns a snapshot of how many times the read and the write wakers of each registered file descriptor
+    /// were woken, as `(fd, reads, writes)` tuples.
+    ///
+    /// Sampling this periodically (e.g. from a monitoring task) reveals file descriptors which are woken
+    /// excessively often, which is a typical symptom of a busy-looping task. The counters wrap around on overflow,
+    /// so compare the samples with `wrapping_sub`.
+    #[cfg(feature = "metrics")]
+    pub fn wake_counts(&self) -> Vec<(RawFd, u32, u32)> {
+        self.registrations
+            .lock()
+            .unwrap()
+            .vec
+            .iter()
+            .map(|registration| {
+                (
+                    registration.fd,
+                    registration.wakes[Event::Read as usize],
+                    registration.wakes[Event::Write as usize],
+                )
+            })
+            .collect()
+    }
+
+    /// Runs the reactor until it fails, and then fails all pending and future operations with its error.
+    fn serve(&self) -> io::Result<()> {
+        let result = self.run();
+
+        if let Err(err) = &result {
+            error!("Reactor failed: {err}");
+
+            self.apply(|inner| {
+                inner.fail(io::Error::new(err.kind(), err.to_string()));
+
+                Ok(())
+            })
+            .unwrap();
+        }
+
+        result
+    }
+
     fn run(&self) -> io::Result<()> {
         if !self.lock(|mut guard| guard.create_notification())? {
             Err(ErrorKind::AlreadyExists)?;
         }
 
+        let (event_fd, debounce) = self.lock(|guard| {
+            Ok((
+                guard.event_fd.as_ref().map(|event_fd| event_fd.as_raw_fd()),
+                guard.config.notify_debounce,
+            ))
+        })?;
+
         debug!("Running");
 
-        let mut fds = Fds::new();
-        let mut update = false;
+        // Declared before the poller lock, so that it is dropped after it
+        let _teardown = Teardown(self);
+
+        let mut poller = self.poller.lock().unwrap();
 
         let result = loop {
-            let max = self.apply(|inner| {
-                if !update {
-                    update = true;
-                } else {
-                    inner.update_events(&fds)?;
-                }
+            // Acknowledges the pending modifications, as the poller now incorporates them
+            let timeout = self.apply(|inner| {
+                inner.sync_poller(&mut *poller)?;
+                inner.complete_pass();
 
-                inner.set_fds(&mut fds)
+                Ok(inner.next_timeout(None))
             });
 
-            let result = match max {
+            let ready = match timeout {
+                Ok(timeout) => poller.wait(timeout),
                 Err(err) => Err(err),
-                Ok(None) => unreachable!("EventFD is not there?"),
-                Ok(Some(max)) => {
-                    trace!("Start select");
-
-                    let result = syscall_los!(unsafe {
-                        sys::select(
-                            max + 1,
-                            fds.read.assume_init_mut(),
-                            fds.write.assume_init_mut(),
-                            fds.except.assume_init_mut(),
-                            core::ptr::null_mut(),
-                        )
-                    });
-
-                    trace!("End select");
-
-                    result.map(|_| ())
-                }
             };
 
+            let ready = match ready {
+                Ok(ready) => ready,
+                Err(err) => break Err(err),
+            };
+
+            #[cfg(feature = "metrics")]
+            let select_end = Instant::now();
+
+            if let (Some(debounce), Some(event_fd)) = (debounce, event_fd) {
+                if ready.iter().any(|ready| ready.fd == event_fd) {
+                    // Give other notifications in the burst a chance to accumulate
+                    trace!("Debouncing notification");
+
+                    std::thread::sleep(debounce);
+                }
+            }
+
+            let result = self.lock(|mut guard| {
+                guard.update_events(ready)?;
+
+                #[cfg(feature = "metrics")]
+                guard.record_pass(select_end);
+
+                Ok(())
+            });
+
             if result.is_err() {
                 break result;
             }
         };
 
-        if !self.lock(|mut guard| guard.destroy_notification())? {
+        if !self.lock(|mut guard| guard.destroy_notification(&mut *poller))? {
             Err(ErrorKind::NotFound)?;
         }
 
         result
     }
 
+    /// Tears down the reactor after the reactor thread panicked (e.g. in a callback or in the pass hook).
+    ///
+    /// Closes the notification file descriptor, which would otherwise leak, and records the failure, so that
+    /// pending and subsequent operations fail with an error, rather than hanging or panicking on the poisoned locks.
+    fn teardown_after_panic(&self) {
+        let mut guard = self
+            .registrations
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+
+        {
+            let mut poller = self.poller.lock().unwrap_or_else(|err| err.into_inner());
+
+            if let Err(err) = guard.destroy_notification(&mut *poller) {
+                warn!("Failed to close the notification FD: {err}");
+            }
+
+            self.poller.clear_poison();
+        }
+
+        error!("Reactor panicked");
+
+        guard.fail(io::Error::other("the reactor thread panicked"));
+        guard.waiting = 0;
+
+        self.registrations.clear_poison();
+
+        self.condvar.notify_all();
+    }
+
+    fn enter_inline(&self) -> io::Result<()> {
+        if !self.started.swap(true, Ordering::SeqCst) {
+            info!("Starting reactor in inline mode");
+
+            self.inline.store(true, Ordering::SeqCst);
+        } else if !self.inline.load(Ordering::SeqCst) {
+            Err(io::Error::other(
+                "the reactor is running on its own thread and cannot be polled inline",
+            ))?;
+        }
+
+        Ok(())
+    }
+
     fn modify<F, R>(&self, f: F) -> io::Result<R>
     where
         F: FnOnce(&mut Registrations<N>) -> io::Result<R>,
     {
+        if !self.started.load(Ordering::SeqCst) {
+            // Nobody would ever acknowledge the modification, so fail rather than hang
+            Err(io::Error::other(
+                "the reactor is not started; call `Reactor::start` first",
+            ))?;
+        }
+
         self.lock(|mut guard| {
+            if let Some(err) = guard.failure() {
+                Err(err)?;
+            }
+
             guard.waiting += 1;
 
+            #[cfg(feature = "metrics")]
+            let start = Instant::now();
+
             let result = f(&mut guard);
 
+            #[cfg(feature = "metrics")]
+            guard.record_modify(start.elapsed());
+
             guard.notify()?;
 
-            let _guard = self
-                .condvar
-                .wait_while(guard, |registrations| registrations.waiting > 0)
-                .unwrap();
+            if self.inline.load(Ordering::SeqCst) {
+                // Nobody to wait for, as the reactor is polled by the user, possibly on this very thread
+                guard.waiting -= 1;
+
+                return result;
+            }
+
+            let guard = if let Some(timeout) = guard.config.modify_timeout {
+                let (guard, wait) = self
+                    .condvar
+                    .wait_timeout_while(guard, timeout, |registrations| registrations.waiting > 0)
+                    .unwrap();
+
+                if wait.timed_out() {
+                    warn!("The reactor did not acknowledge a modification within {timeout:?}");
+
+                    Err(io::Error::new(
+                        ErrorKind::TimedOut,
+                        "the reactor did not acknowledge the modification in time",
+                    ))?;
+                }
+
+                guard
+            } else {
+                self.condvar
+                    .wait_while(guard, |registrations| registrations.waiting > 0)
+                    .unwrap()
+            };
+
+            if let Some(err) = guard.failure() {
+                // The reactor failed before it could acknowledge the modification
+                Err(err)?;
+            }
 
             result
         })
@@ -468,4 +2265,260 @@ impl<const N: usize> Reactor<N> {
     }
 }
 
-pub static REACTOR: Reactor<MAX_REGISTRATIONS> = Reactor::new();
+/// Removes the deadline of a [`Reactor::ready`] future which is dropped before it resolves.
+struct DeadlineGuard<'a, const N: usize, P> {
+    reactor: &'a Reactor<N, P>,
+    fd: RawFd,
+    interest: EnumSet<Event>,
+    /// The deadline currently set in the reactor, if any
+    deadline: Option<Instant>,
+}
+
+impl<const N: usize, P> Drop for DeadlineGuard<'_, N, P> {
+    fn drop(&mut self) {
+        if let Some(deadline) = self.deadline {
+            // No need to notify the reactor: at worst, it wakes up once more at the old deadline.
+            // The file descriptor might have been deregistered in the meantime
+            let _ = self.reactor.registrations.lock().unwrap().clear_deadline(
+                self.fd,
+                self.interest,
+                deadline,
+            );
+        }
+    }
+}
+
+#[cfg(all(not(feature = "poll"), not(target_vendor = "apple")))]
+type ReactorPoller = SelectPoller<MAX_REGISTRATIONS>;
+// One more slot for the notification file descriptor
+#[cfg(feature = "poll")]
+type ReactorPoller = crate::poller::PollPoller<{ MAX_REGISTRATIONS + 1 }>;
+// macOS and iOS have no `eventfd`, and `select` is notoriously limited there, so use their native API
+#[cfg(all(not(feature = "poll"), target_vendor = "apple"))]
+type ReactorPoller = crate::poller::KqueuePoller<{ MAX_REGISTRATIONS + 1 }>;
+
+pub static REACTOR: Reactor<MAX_REGISTRATIONS, ReactorPoller> =
+    Reactor::with_poller(ReactorPoller::new());
+
+/// A single-threaded reactor, which is driven inline by the executor with [`LocalReactor::poll_once`].
+///
+/// Unlike [`Reactor`], this reactor does not need a mutex and a condition variable for synchronizing with
+/// a reactor thread, which saves code size and lock acquisitions. In exchange, it is `!Sync` and can only be used
+/// from the thread which owns it, so it is suitable for single-threaded executors (e.g. on single-core MCUs).
+///
+/// Since [`Async`](crate::Async) always uses the global [`REACTOR`], this reactor is used with raw file descriptors
+/// instead, which need to be in non-blocking mode:
+///
+/// ```no_run
+/// use std::net::UdpSocket;
+/// use std::os::fd::AsRawFd;
+///
+/// use async_io_mini::{Event, LocalReactor};
+///
+/// let reactor = LocalReactor::<8>::new();
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// socket.set_nonblocking(true)?;
+///
+/// reactor.register(socket.as_raw_fd())?;
+///
+/// // In the executor loop, after polling all tasks (which use `LocalReactor::poll_ready`
+/// // to wait for the socket to become readable)
+/// reactor.poll_once(None)?;
+/// # std::io::Result::Ok(())
+/// ```
+pub struct LocalReactor<const N: usize, P = SelectPoller<N>> {
+    registrations: core::cell::RefCell<Registrations<N>>,
+    poller: core::cell::RefCell<P>,
+}
+
+impl<const N: usize> LocalReactor<N> {
+    /// Creates a new local reactor, which waits for I/O events with the `select` poller.
+    pub const fn new() -> Self {
+        Self::with_poller(SelectPoller::new())
+    }
+}
+
+impl<const N: usize, P: Poller> LocalReactor<N, P> {
+    /// Creates a new local reactor, which waits for I/O events with the provided poller.
+    pub const fn with_poller(poller: P) -> Self {
+        const { assert_fd_limit::<P>(N) };
+
+        Self {
+            registrations: core::cell::RefCell::new(Registrations::new(P::FD_LIMIT)),
+            poller: core::cell::RefCell::new(poller),
+        }
+    }
+
+    /// Registers a file descriptor in the reactor.
+    pub fn register(&self, fd: RawFd) -> io::Result<()> {
+        self.registrations.borrow_mut().register(fd, false)
+    }
+
+    /// Registers a file descriptor in the reactor, together with an opaque user data token.
+    ///
+    /// The semantics are the same as those of [`Reactor::register_with_data`].
+    pub fn register_with_data(&self, fd: RawFd, data: usize) -> io::Result<()> {
+        let mut regs = self.registrations.borrow_mut();
+
+        regs.register(fd, false)?;
+        regs.set_data(fd, data)
+    }
+
+    /// Returns the user data token of the registered file descriptor.
+    pub fn user_data(&self, fd: RawFd) -> io::Result<usize> {
+        self.registrations.borrow().data(fd)
+    }
+
+    /// Registers a file descriptor in the reactor, which is known to be already ready for the `initial` events.
+    ///
+    /// The semantics are the same as those of [`Reactor::register_with_readiness`].
+    pub fn register_with_readiness(&self, fd: RawFd, initial: EnumSet<Event>) -> io::Result<()> {
+        let mut regs = self.registrations.borrow_mut();
+
+        regs.register(fd, false)?;
+        regs.set_readiness(fd, initial)
+    }
+
+    /// Sets aside `n` registration slots for [`LocalReactor::register_reserved`].
+    ///
+    /// The semantics are the same as those of [`Reactor::reserve`].
+    pub fn reserve(&self, n: usize) -> io::Result<()> {
+        self.registrations.borrow_mut().reserve(n)
+    }
+
+    /// Registers a file descriptor in the reactor, using one of the reserved slots if no other slot is free.
+    pub fn register_reserved(&self, fd: RawFd) -> io::Result<()> {
+        self.registrations.borrow_mut().register(fd, true)
+    }
+
+    /// Deregisters a file descriptor from the reactor.
+    ///
+    /// The file descriptor must be deregistered before it is closed.
+    pub fn deregister(&self, fd: RawFd) -> io::Result<()> {
+        self.registrations.borrow_mut().deregister(fd)
+    }
+
+    /// Polls the registered file descriptor for readiness for the provided event.
+    ///
+    /// Returns [`Poll::Ready`] if the reactor has observed the file descriptor being ready since the last time
+    /// this method returned [`Poll::Pending`], consuming the readiness. Otherwise, arms the waker of the context
+    /// to be woken by a subsequent [`LocalReactor::poll_once`] call.
+    pub fn poll_ready(
+        &self,
+        fd: RawFd,
+        event: Event,
+        cx: &mut core::task::Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut regs = self.registrations.borrow_mut();
+
+        match regs.fetch(fd, event) {
+            Ok(true) => Poll::Ready(Ok(())),
+            Ok(false) => match regs.set(fd, event, cx.waker()) {
+                Ok(()) => Poll::Pending,
+                Err(err) => Poll::Ready(Err(err)),
+            },
+            Err(err) => Poll::Ready(Err(err)),
+        }
+    }
+
+    /// Waits until the registered file descriptor is ready for the provided event.
+    pub async fn ready(&self, fd: RawFd, event: Event) -> io::Result<()> {
+        poll_fn(|cx| self.poll_ready(fd, event, cx)).await
+    }
+
+    /// Runs a single reactor pass.
+    ///
+    /// The semantics are the same as those of [`Reactor::poll_once`], except that the pass cannot be interrupted
+    /// by a notification, as there is no other thread to notify the reactor.
+    pub fn poll_once(&self, timeout: Option<Duration>) -> io::Result<usize> {
+        let mut poller = self.poller.borrow_mut();
+
+        let timeout = {
+            let mut regs = self.registrations.borrow_mut();
+
+            regs.sync_poller(&mut *poller)?;
+            regs.next_timeout(timeout)
+        };
+
+        let ready = poller.wait(timeout)?;
+
+        #[cfg(feature = "metrics")]
+        let select_end = Instant::now();
+
+        let mut regs = self.registrations.borrow_mut();
+
+        let woken = regs.update_events(ready)?;
+
+        #[cfg(feature = "metrics")]
+        regs.record_pass(select_end);
+
+        Ok(woken)
+    }
+}
+
+impl<const N: usize> Default for LocalReactor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tears down the reactor if the reactor thread unwinds from [`Reactor::run`].
+struct Teardown<'a, const N: usize, P: Poller + Send>(&'a Reactor<N, P>);
+
+impl<const N: usize, P: Poller + Send> Drop for Teardown<'_, N, P> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.teardown_after_panic();
+        }
+    }
+}
+
+/// Creates the notification "self-pipe", returning its read end and - if separate - its write end.
+///
+/// On the ESP-IDF, where pipes are not generally available, this is a UDP socket bound to the loopback interface
+/// and connected to itself, so that lwIP serves as the pipe.
+#[cfg(any(feature = "notify-pipe", target_vendor = "apple"))]
+fn notification_pipe() -> io::Result<(OwnedFd, Option<OwnedFd>)> {
+    #[cfg(not(target_os = "espidf"))]
+    {
+        let mut fds = [0; 2];
+
+        syscall_los!(unsafe { sys::pipe(fds.as_mut_ptr()) })?;
+
+        let fds = unsafe { [OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])] };
+
+        for fd in &fds {
+            let flags = syscall_los!(unsafe { sys::fcntl(fd.as_raw_fd(), sys::F_GETFL) })?;
+            syscall_los!(unsafe {
+                sys::fcntl(fd.as_raw_fd(), sys::F_SETFL, flags | sys::O_NONBLOCK)
+            })?;
+            syscall_los!(unsafe { sys::fcntl(fd.as_raw_fd(), sys::F_SETFD, sys::FD_CLOEXEC) })?;
+        }
+
+        let [read_fd, write_fd] = fds;
+
+        Ok((read_fd, Some(write_fd)))
+    }
+
+    #[cfg(target_os = "espidf")]
+    {
+        let socket = std::net::UdpSocket::bind((std::net::Ipv4Addr::LOCALHOST, 0))?;
+
+        socket.connect(socket.local_addr()?)?;
+        socket.set_nonblocking(true)?;
+
+        Ok((socket.into(), None))
+    }
+}
+
+/// Fails the build if the poller cannot watch `registrations` file descriptors, i.e. if more registrations than
+/// `FD_SETSIZE` are requested from the `select` backend.
+const fn assert_fd_limit<P: Poller>(registrations: usize) {
+    if let Some(fd_limit) = P::FD_LIMIT {
+        assert!(
+            registrations <= fd_limit as usize,
+            "The select() backend cannot handle more than FD_SETSIZE registrations; enable the `poll` feature and use `PollPoller` for more"
+        );
+    }
+}
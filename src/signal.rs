@@ -0,0 +1,136 @@
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+use super::io::{Async, IoSafe};
+use super::sys;
+use super::syscall_los;
+
+/// A Linux `signalfd` file descriptor, for receiving signals as I/O events.
+///
+/// Wrap it in [`Async`] to await signals the same way as I/O:
+///
+/// ```no_run
+/// use async_io_mini::{Async, SignalFd};
+///
+/// # futures_lite::future::block_on(async {
+/// let signals = Async::<SignalFd>::with_signals(&[libc::SIGTERM, libc::SIGINT])?;
+///
+/// let signal = signals.next_signal().await?;
+/// println!("Received signal {}, shutting down", signal.signo);
+/// # std::io::Result::Ok(()) });
+/// ```
+///
+/// # Signal masks
+///
+/// For the signals to be delivered to the `signalfd` file descriptor, rather than to their handlers (or the default
+/// action), they need to be blocked. [`SignalFd::new`] blocks them for the calling thread only. Since process-directed
+/// signals are delivered to an arbitrary thread which does not block them, the signal file descriptor should be created
+/// early in the main thread, before any other threads (including the reactor thread) are spawned, so that these
+/// inherit the signal mask.
+#[derive(Debug)]
+pub struct SignalFd {
+    fd: OwnedFd,
+}
+
+impl SignalFd {
+    /// Blocks the provided signals for the calling thread and creates a non-blocking `signalfd` file descriptor
+    /// for them.
+    pub fn new(signals: &[sys::c_int]) -> io::Result<Self> {
+        let mut mask = core::mem::MaybeUninit::<sys::sigset_t>::uninit();
+
+        syscall_los!(unsafe { sys::sigemptyset(mask.as_mut_ptr()) })?;
+
+        for signal in signals {
+            syscall_los!(unsafe { sys::sigaddset(mask.as_mut_ptr(), *signal) })?;
+        }
+
+        let mask = unsafe { mask.assume_init() };
+
+        let result = unsafe { sys::pthread_sigmask(sys::SIG_BLOCK, &mask, core::ptr::null_mut()) };
+        if result != 0 {
+            Err(io::Error::from_raw_os_error(result))?;
+        }
+
+        let fd = syscall_los!(unsafe {
+            sys::signalfd(-1, &mask, sys::SFD_NONBLOCK | sys::SFD_CLOEXEC)
+        })?;
+
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    /// Reads the next pending signal, if any.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::WouldBlock`] if there is no pending signal.
+    pub fn read_signal(&self) -> io::Result<Signal> {
+        let mut info = core::mem::MaybeUninit::<sys::signalfd_siginfo>::uninit();
+
+        let len = syscall_los!(unsafe {
+            sys::read(
+                self.fd.as_raw_fd(),
+                info.as_mut_ptr() as *mut _,
+                core::mem::size_of::<sys::signalfd_siginfo>(),
+            )
+        })?;
+
+        if len as usize != core::mem::size_of::<sys::signalfd_siginfo>() {
+            Err(io::ErrorKind::UnexpectedEof)?;
+        }
+
+        let info = unsafe { info.assume_init() };
+
+        Ok(Signal {
+            signo: info.ssi_signo as _,
+            pid: info.ssi_pid,
+            uid: info.ssi_uid,
+        })
+    }
+}
+
+/// A signal received via a [`SignalFd`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Signal {
+    /// The signal number, e.g. `libc::SIGTERM`.
+    pub signo: sys::c_int,
+    /// The PID of the sender of the signal.
+    pub pid: u32,
+    /// The real UID of the sender of the signal.
+    pub uid: u32,
+}
+
+impl AsFd for SignalFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for SignalFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl From<SignalFd> for OwnedFd {
+    fn from(signal_fd: SignalFd) -> Self {
+        signal_fd.fd
+    }
+}
+
+unsafe impl IoSafe for SignalFd {}
+
+impl Async<SignalFd> {
+    /// Blocks the provided signals for the calling thread, creates a `signalfd` file descriptor for them and
+    /// registers it in the reactor.
+    ///
+    /// See [`SignalFd`] for the caveats regarding the signal masks of the other threads.
+    pub fn with_signals(signals: &[sys::c_int]) -> io::Result<Async<SignalFd>> {
+        Async::new_nonblocking(SignalFd::new(signals)?)
+    }
+
+    /// Waits for the next signal.
+    pub async fn next_signal(&self) -> io::Result<Signal> {
+        self.read_with(|io| io.read_signal()).await
+    }
+}
@@ -4,7 +4,8 @@ use core::pin::Pin;
 use core::task::{Context, Poll, Waker};
 use core::time::Duration;
 
-use std::time::Instant;
+use std::io;
+use std::time::{Instant, SystemTime};
 
 /// A future or stream that emits timed events.
 ///
@@ -449,3 +450,45 @@ impl futures_lite::Stream for Timer {
         Poll::Ready(Some(when))
     }
 }
+
+/// Runs the future until the provided wall-clock (real-time clock) deadline, e.g. the top of the next minute.
+///
+/// Fails with an error of kind [`io::ErrorKind::TimedOut`] if the future does not complete before the deadline.
+/// If the deadline is already in the past, the future is still polled once.
+///
+/// The deadline is converted to a monotonic [`Instant`] when this function is called, and the monotonic timer
+/// takes over from there. Therefore, stepping the real-time clock afterwards (e.g. when it is synchronized with
+/// SNTP) does not move the deadline.
+///
+/// # Examples
+///
+/// ```
+/// use async_io_mini::timeout_at_system;
+/// use std::time::{Duration, SystemTime};
+///
+/// # futures_lite::future::block_on(async {
+/// let deadline = SystemTime::now() + Duration::from_millis(100);
+///
+/// let result = timeout_at_system(deadline, core::future::pending::<()>()).await;
+/// assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+/// # });
+/// ```
+pub fn timeout_at_system<F: Future>(
+    deadline: SystemTime,
+    f: F,
+) -> impl Future<Output = io::Result<F::Output>> {
+    let remaining = deadline
+        .duration_since(SystemTime::now())
+        .unwrap_or(Duration::ZERO);
+
+    // Snapshotted right away, rather than when the returned future is first polled
+    let deadline = Instant::now().checked_add(remaining);
+
+    async move {
+        match deadline {
+            Some(deadline) => crate::io::timeout_at(async { Ok(f.await) }, deadline).await,
+            // Too far in the future to ever pass
+            None => Ok(f.await),
+        }
+    }
+}
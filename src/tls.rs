@@ -0,0 +1,299 @@
+use core::future::poll_fn;
+use core::task::{Context, Poll};
+
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd};
+
+use rustls::Connection;
+
+use crate::reactor::{Event, REACTOR};
+use crate::{ready, Async};
+
+/// A TLS stream, which drives a non-blocking [`rustls`] connection (a `ClientConnection` or a `ServerConnection`)
+/// against the reactor.
+///
+/// The TLS records are read from - and written to - the underlying [`Async`] transport (e.g. an
+/// [`Async<TcpStream>`](std::net::TcpStream)) whenever rustls [wants to read](Connection::wants_read) or
+/// [wants to write](Connection::wants_write). Since the handshake alternates between needing read and write
+/// readiness of the transport, the interest is switched with a single reactor round-trip (see
+/// [`Reactor::swap_interest`](crate::Reactor::swap_interest)).
+///
+/// The decrypted stream is exposed with the [`read`](TlsStream::read) and [`write`](TlsStream::write) methods,
+/// as well as with the `AsyncRead` and `AsyncWrite` traits (with the `futures-io` feature). The handshake
+/// is completed implicitly on first use, or explicitly with [`handshake`](TlsStream::handshake).
+///
+/// Only available with the `rustls` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::TcpStream;
+/// use std::sync::Arc;
+///
+/// use async_io_mini::{Async, TlsStream};
+///
+/// # fn config() -> Arc<rustls::ClientConfig> { unimplemented!() }
+/// # futures_lite::future::block_on(async {
+/// let stream = Async::<TcpStream>::connect(([127, 0, 0, 1], 8443)).await?;
+///
+/// let conn = rustls::ClientConnection::new(config(), "localhost".try_into().unwrap())
+///     .map_err(std::io::Error::other)?;
+///
+/// let mut stream = TlsStream::new(stream, conn);
+///
+/// stream.write_all(b"ping").await?;
+///
+/// let mut buf = [0; 4];
+/// stream.read(&mut buf).await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+pub struct TlsStream<T: AsFd> {
+    io: Async<T>,
+    conn: Connection,
+    /// The event of the transport the waker is currently armed for
+    armed: Option<Event>,
+}
+
+impl<T> TlsStream<T>
+where
+    T: AsFd,
+    for<'a> &'a T: Read + Write,
+{
+    /// Creates a TLS stream over the transport, with the provided client or server connection.
+    pub fn new(io: Async<T>, conn: impl Into<Connection>) -> Self {
+        Self {
+            io,
+            conn: conn.into(),
+            armed: None,
+        }
+    }
+
+    /// Gets a reference to the underlying transport.
+    pub fn get_ref(&self) -> &Async<T> {
+        &self.io
+    }
+
+    /// Gets a reference to the rustls connection (e.g. to inspect the negotiated ALPN protocol).
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Unwraps the underlying transport and the rustls connection.
+    pub fn into_inner(self) -> (Async<T>, Connection) {
+        (self.io, self.conn)
+    }
+
+    /// Completes the handshake, if it is not complete yet.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::UnexpectedEof`] if the peer closes the transport mid-handshake,
+    /// and of kind [`io::ErrorKind::InvalidData`] if the handshake fails (e.g. the certificate is not trusted).
+    pub async fn handshake(&mut self) -> io::Result<()> {
+        poll_fn(|cx| self.poll_handshake(cx)).await
+    }
+
+    /// Reads some decrypted bytes from the stream.
+    ///
+    /// Returns 0 once the peer has closed the TLS session cleanly (with a `close_notify` alert), and an error of
+    /// kind [`io::ErrorKind::UnexpectedEof`] if the transport is closed without it.
+    pub async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        poll_fn(|cx| self.poll_read_plaintext(cx, buf)).await
+    }
+
+    /// Encrypts and writes some bytes to the stream, returning how many of them were written.
+    ///
+    /// The encrypted records are flushed to the transport before returning.
+    pub async fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        poll_fn(|cx| self.poll_write_plaintext(cx, buf)).await
+    }
+
+    /// Encrypts and writes all of the bytes to the stream.
+    pub async fn write_all(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            let len = self.write(buf).await?;
+
+            if len == 0 {
+                Err(io::ErrorKind::WriteZero)?;
+            }
+
+            buf = &buf[len..];
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the pending encrypted records to the transport.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        poll_fn(|cx| self.poll_flush_tls(cx)).await
+    }
+
+    /// Closes the TLS session cleanly, by sending a `close_notify` alert to the peer.
+    ///
+    /// The transport itself is not shut down.
+    pub async fn close(&mut self) -> io::Result<()> {
+        self.conn.send_close_notify();
+
+        self.flush().await
+    }
+
+    fn poll_handshake(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.conn.is_handshaking() {
+            if !ready!(self.poll_io(cx))? {
+                Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the transport was closed during the handshake",
+                ))?;
+            }
+        }
+
+        // E.g. the final handshake message of a TLS 1.2 client, or the session tickets of a TLS 1.3 server
+        self.poll_flush_tls(cx)
+    }
+
+    fn poll_read_plaintext(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.poll_handshake(cx))?;
+
+        loop {
+            match self.conn.reader().read(buf) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    if !ready!(self.poll_io(cx))? {
+                        // After the transport EOF, the reader normally reports how the session ended instead
+                        Err(io::ErrorKind::UnexpectedEof)?;
+                    }
+                }
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+
+    fn poll_write_plaintext(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.poll_handshake(cx))?;
+
+        // Keep the buffering of rustls bounded, by flushing the previous records first
+        ready!(self.poll_flush_tls(cx))?;
+
+        let len = self.conn.writer().write(buf)?;
+
+        // The plaintext is accepted already, so the records are flushed by the next call, if this one is pending
+        let _ = self.poll_flush_tls(cx)?;
+
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush_tls(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.conn.wants_write() {
+            match self.conn.write_tls(&mut self.io.get_ref()) {
+                Ok(_) => (),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    ready!(self.poll_event(cx, Event::Write))?
+                }
+                Err(err) => Err(err)?,
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Writes the pending records, or - if there are none - reads and processes the incoming records.
+    ///
+    /// Resolves to `false` if the transport has reached EOF.
+    fn poll_io(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+        loop {
+            if self.conn.wants_write() {
+                ready!(self.poll_flush_tls(cx))?;
+            } else if self.conn.wants_read() {
+                match self.conn.read_tls(&mut self.io.get_ref()) {
+                    Ok(0) => return Poll::Ready(Ok(false)),
+                    Ok(_) => {
+                        if let Err(err) = self.conn.process_new_packets() {
+                            // Best-effort attempt to tell the peer about the failure with an alert
+                            let _ = self.conn.write_tls(&mut self.io.get_ref());
+
+                            Err(io::Error::new(io::ErrorKind::InvalidData, err))?;
+                        }
+
+                        return Poll::Ready(Ok(true));
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        ready!(self.poll_event(cx, Event::Read))?
+                    }
+                    Err(err) => Err(err)?,
+                }
+            } else {
+                // Nothing to exchange, e.g. after a clean close
+                return Poll::Ready(Ok(false));
+            }
+        }
+    }
+
+    /// Waits for the transport to become ready for the event, switching the armed waker from the other
+    /// event in the same reactor round-trip, if necessary.
+    fn poll_event(&mut self, cx: &mut Context<'_>, event: Event) -> Poll<io::Result<()>> {
+        let fd = self.io.as_fd().as_raw_fd();
+
+        let ready = match self.armed {
+            Some(armed) if armed != event => REACTOR.swap_interest(fd, armed, event, cx.waker())?,
+            _ => REACTOR.fetch_or_set(fd, event, cx.waker())?,
+        };
+
+        if ready {
+            self.armed = None;
+
+            Poll::Ready(Ok(()))
+        } else {
+            self.armed = Some(event);
+
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T> futures_io::AsyncRead for TlsStream<T>
+where
+    T: AsFd + Unpin,
+    for<'a> &'a T: Read + Write,
+{
+    fn poll_read(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_read_plaintext(cx, buf)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<T> futures_io::AsyncWrite for TlsStream<T>
+where
+    T: AsFd + Unpin,
+    for<'a> &'a T: Read + Write,
+{
+    fn poll_write(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_write_plaintext(cx, buf)
+    }
+
+    fn poll_flush(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_flush_tls(cx)
+    }
+
+    fn poll_close(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        // Sending the alert more than once (i.e. when the close is polled again) is harmless
+        this.conn.send_close_notify();
+
+        this.poll_flush_tls(cx)
+    }
+}
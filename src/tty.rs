@@ -0,0 +1,228 @@
+use std::ffi::CString;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use super::io::{Async, IoSafe};
+use super::sys;
+use super::syscall_los;
+
+/// A serial port (UART) or a TTY file descriptor.
+///
+/// Wrap it in [`Async`] to read from and write to the serial port asynchronously:
+///
+/// ```no_run
+/// use async_io_mini::{Async, Tty};
+///
+/// # futures_lite::future::block_on(async {
+/// let tty = Async::<Tty>::open("/dev/ttyUSB0")?;
+///
+/// tty.get_ref().set_raw()?;
+/// tty.get_ref().set_baud_rate(115200)?;
+///
+/// let mut buf = [0u8; 128];
+/// let len = tty.read(&mut buf).await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+///
+/// # Termios
+///
+/// The termios configuration methods are synchronous. Note that the file descriptor is in non-blocking
+/// mode, so reads are driven by the reactor rather than by `VMIN`/`VTIME`.
+///
+/// The termios methods are not available on the ESP-IDF, because the `libc` crate does not provide
+/// termios bindings for it. Configure the UART with the ESP-IDF UART driver instead.
+#[derive(Debug)]
+pub struct Tty {
+    fd: OwnedFd,
+}
+
+impl Tty {
+    /// Opens the serial port or TTY at the provided path in non-blocking mode.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|_| io::ErrorKind::InvalidInput)?;
+
+        let fd = syscall_los!(unsafe {
+            sys::open(
+                path.as_ptr(),
+                sys::O_RDWR | sys::O_NOCTTY | sys::O_NONBLOCK | sys::O_CLOEXEC,
+            )
+        })?;
+
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    /// Puts the TTY in raw mode (as per `cfmakeraw`), i.e. no echo, no line editing and
+    /// no processing of special characters.
+    #[cfg(not(target_os = "espidf"))]
+    pub fn set_raw(&self) -> io::Result<()> {
+        self.modify_termios(|termios| {
+            unsafe {
+                sys::cfmakeraw(termios);
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Sets the input and output baud rate of the TTY.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the baud rate is not one of the
+    /// standard rates supported by the platform.
+    #[cfg(not(target_os = "espidf"))]
+    pub fn set_baud_rate(&self, baud_rate: u32) -> io::Result<()> {
+        let speed = speed(baud_rate)?;
+
+        self.modify_termios(|termios| {
+            syscall_los!(unsafe { sys::cfsetispeed(termios, speed) })?;
+            syscall_los!(unsafe { sys::cfsetospeed(termios, speed) })?;
+
+            Ok(())
+        })
+    }
+
+    /// Sets the `VMIN` and `VTIME` termios parameters of the TTY.
+    ///
+    /// These only influence blocking reads, so they only matter if the TTY is taken out of
+    /// the [`Async`] wrapper and put back in blocking mode.
+    #[cfg(not(target_os = "espidf"))]
+    pub fn set_vmin_vtime(&self, vmin: u8, vtime: u8) -> io::Result<()> {
+        self.modify_termios(|termios| {
+            termios.c_cc[sys::VMIN] = vmin as _;
+            termios.c_cc[sys::VTIME] = vtime as _;
+
+            Ok(())
+        })
+    }
+
+    #[cfg(not(target_os = "espidf"))]
+    fn modify_termios<F>(&self, f: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut sys::termios) -> io::Result<()>,
+    {
+        let mut termios = core::mem::MaybeUninit::<sys::termios>::uninit();
+
+        syscall_los!(unsafe { sys::tcgetattr(self.fd.as_raw_fd(), termios.as_mut_ptr()) })?;
+
+        let mut termios = unsafe { termios.assume_init() };
+
+        f(&mut termios)?;
+
+        syscall_los!(unsafe { sys::tcsetattr(self.fd.as_raw_fd(), sys::TCSANOW, &termios) })?;
+
+        Ok(())
+    }
+}
+
+impl AsFd for Tty {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for Tty {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl From<OwnedFd> for Tty {
+    fn from(fd: OwnedFd) -> Self {
+        Self { fd }
+    }
+}
+
+impl From<Tty> for OwnedFd {
+    fn from(tty: Tty) -> Self {
+        tty.fd
+    }
+}
+
+impl Read for &Tty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = syscall_los!(unsafe {
+            sys::read(self.fd.as_raw_fd(), buf.as_mut_ptr() as *mut _, buf.len())
+        })?;
+
+        Ok(len as usize)
+    }
+}
+
+impl Write for &Tty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = syscall_los!(unsafe {
+            sys::write(self.fd.as_raw_fd(), buf.as_ptr() as *const _, buf.len())
+        })?;
+
+        Ok(len as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Read for Tty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl Write for Tty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+unsafe impl IoSafe for Tty {}
+
+impl Async<Tty> {
+    /// Opens the serial port or TTY at the provided path and registers it in the reactor.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Async<Tty>> {
+        Async::new_nonblocking(Tty::open(path)?)
+    }
+
+    /// Reads some bytes from the TTY.
+    ///
+    /// Returns the number of bytes read.
+    pub async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|mut io| io.read(buf)).await
+    }
+
+    /// Writes some bytes to the TTY.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(|mut io| io.write(buf)).await
+    }
+}
+
+#[cfg(not(target_os = "espidf"))]
+fn speed(baud_rate: u32) -> io::Result<sys::speed_t> {
+    let speed = match baud_rate {
+        1200 => sys::B1200,
+        2400 => sys::B2400,
+        4800 => sys::B4800,
+        9600 => sys::B9600,
+        19200 => sys::B19200,
+        38400 => sys::B38400,
+        57600 => sys::B57600,
+        115200 => sys::B115200,
+        230400 => sys::B230400,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        460800 => sys::B460800,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        921600 => sys::B921600,
+        _ => Err(io::ErrorKind::InvalidInput)?,
+    };
+
+    Ok(speed)
+}
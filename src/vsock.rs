@@ -0,0 +1,345 @@
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+use super::io::{Async, IoSafe};
+use super::sys;
+use super::syscall_los;
+
+/// The address of a vsock (`AF_VSOCK`) socket, i.e. a context ID (CID) and a port.
+///
+/// The well-known context IDs are available as the `VMADDR_CID_*` constants of the `libc` crate, e.g.
+/// `libc::VMADDR_CID_HOST` for the host, as seen from a VM, and `libc::VMADDR_CID_ANY` for binding to any CID.
+/// Binding to the port `libc::VMADDR_PORT_ANY` requests an available port from the OS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VsockAddr {
+    cid: u32,
+    port: u32,
+}
+
+impl VsockAddr {
+    /// Creates a new vsock address from a context ID and a port.
+    pub const fn new(cid: u32, port: u32) -> Self {
+        Self { cid, port }
+    }
+
+    /// Returns the context ID of the address.
+    pub const fn cid(&self) -> u32 {
+        self.cid
+    }
+
+    /// Returns the port of the address.
+    pub const fn port(&self) -> u32 {
+        self.port
+    }
+
+    fn to_raw(self) -> sys::sockaddr_vm {
+        let mut addr: sys::sockaddr_vm = unsafe { core::mem::zeroed() };
+
+        addr.svm_family = sys::AF_VSOCK as _;
+        addr.svm_cid = self.cid;
+        addr.svm_port = self.port;
+
+        addr
+    }
+
+    fn from_raw(addr: &sys::sockaddr_vm) -> io::Result<Self> {
+        if addr.svm_family as sys::c_int != sys::AF_VSOCK {
+            Err(io::ErrorKind::InvalidInput)?;
+        }
+
+        Ok(Self::new(addr.svm_cid, addr.svm_port))
+    }
+}
+
+/// A vsock (`AF_VSOCK`) stream socket, for communicating between a VM and its host.
+///
+/// The standard library does not model vsock sockets, so this is a thin wrapper around the raw socket.
+/// Wrap it in [`Async`] to connect and to read and write asynchronously:
+///
+/// ```no_run
+/// use async_io_mini::{Async, VsockAddr, VsockStream};
+///
+/// # futures_lite::future::block_on(async {
+/// let stream = Async::<VsockStream>::connect(VsockAddr::new(3, 5000)).await?;
+///
+/// stream.write(b"hello").await?;
+/// # std::io::Result::Ok(()) });
+/// ```
+///
+/// Only available on Linux.
+#[derive(Debug)]
+pub struct VsockStream {
+    fd: OwnedFd,
+}
+
+impl VsockStream {
+    /// Returns the local address of the socket.
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        vsock_addr(|addr, len| unsafe { sys::getsockname(self.fd.as_raw_fd(), addr, len) })
+    }
+
+    /// Returns the address of the peer of the socket.
+    pub fn peer_addr(&self) -> io::Result<VsockAddr> {
+        vsock_addr(|addr, len| unsafe { sys::getpeername(self.fd.as_raw_fd(), addr, len) })
+    }
+
+    /// Shuts down the read half, the write half, or both halves of the connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        let how = match how {
+            Shutdown::Read => sys::SHUT_RD,
+            Shutdown::Write => sys::SHUT_WR,
+            Shutdown::Both => sys::SHUT_RDWR,
+        };
+
+        syscall_los!(unsafe { sys::shutdown(self.fd.as_raw_fd(), how) })?;
+
+        Ok(())
+    }
+}
+
+impl AsFd for VsockStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for VsockStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl From<OwnedFd> for VsockStream {
+    fn from(fd: OwnedFd) -> Self {
+        Self { fd }
+    }
+}
+
+impl From<VsockStream> for OwnedFd {
+    fn from(stream: VsockStream) -> Self {
+        stream.fd
+    }
+}
+
+impl Read for &VsockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = syscall_los!(unsafe {
+            sys::recv(
+                self.fd.as_raw_fd(),
+                buf.as_mut_ptr() as *mut _,
+                buf.len(),
+                0,
+            )
+        })?;
+
+        Ok(len as usize)
+    }
+}
+
+impl Write for &VsockStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = syscall_los!(unsafe {
+            sys::send(
+                self.fd.as_raw_fd(),
+                buf.as_ptr() as *const _,
+                buf.len(),
+                sys::MSG_NOSIGNAL,
+            )
+        })?;
+
+        Ok(len as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Read for VsockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl Write for VsockStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+unsafe impl IoSafe for VsockStream {}
+
+/// A vsock (`AF_VSOCK`) listener socket, accepting [`VsockStream`] connections.
+///
+/// Wrap it in [`Async`] to accept connections asynchronously:
+///
+/// ```no_run
+/// use async_io_mini::{Async, VsockAddr, VsockListener};
+///
+/// # futures_lite::future::block_on(async {
+/// let listener = Async::<VsockListener>::bind(VsockAddr::new(libc::VMADDR_CID_ANY, 5000))?;
+///
+/// let (stream, addr) = listener.accept().await?;
+/// println!("Accepted a connection from CID {}", addr.cid());
+/// # std::io::Result::Ok(()) });
+/// ```
+///
+/// Only available on Linux.
+#[derive(Debug)]
+pub struct VsockListener {
+    fd: OwnedFd,
+}
+
+impl VsockListener {
+    /// Creates a non-blocking listener bound to the provided address.
+    pub fn bind(addr: VsockAddr) -> io::Result<Self> {
+        let fd = socket()?;
+
+        let addr = addr.to_raw();
+
+        syscall_los!(unsafe {
+            sys::bind(
+                fd.as_raw_fd(),
+                &addr as *const _ as *const _,
+                core::mem::size_of::<sys::sockaddr_vm>() as _,
+            )
+        })?;
+
+        syscall_los!(unsafe { sys::listen(fd.as_raw_fd(), 128) })?;
+
+        Ok(Self { fd })
+    }
+
+    /// Accepts a new connection, which is put in non-blocking mode.
+    ///
+    /// Fails with an error of kind [`io::ErrorKind::WouldBlock`] if there is no pending connection.
+    pub fn accept(&self) -> io::Result<(VsockStream, VsockAddr)> {
+        let mut addr = core::mem::MaybeUninit::<sys::sockaddr_vm>::zeroed();
+        let mut addr_len = core::mem::size_of::<sys::sockaddr_vm>() as sys::socklen_t;
+
+        let fd = syscall_los!(unsafe {
+            sys::accept4(
+                self.fd.as_raw_fd(),
+                addr.as_mut_ptr() as *mut _,
+                &mut addr_len,
+                sys::SOCK_NONBLOCK | sys::SOCK_CLOEXEC,
+            )
+        })?;
+
+        let stream = VsockStream::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        let addr = VsockAddr::from_raw(unsafe { addr.assume_init_ref() })?;
+
+        Ok((stream, addr))
+    }
+
+    /// Returns the local address of the listener.
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        vsock_addr(|addr, len| unsafe { sys::getsockname(self.fd.as_raw_fd(), addr, len) })
+    }
+}
+
+impl AsFd for VsockListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for VsockListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl From<OwnedFd> for VsockListener {
+    fn from(fd: OwnedFd) -> Self {
+        Self { fd }
+    }
+}
+
+impl From<VsockListener> for OwnedFd {
+    fn from(listener: VsockListener) -> Self {
+        listener.fd
+    }
+}
+
+impl Async<VsockListener> {
+    /// Creates a listener bound to the provided address.
+    pub fn bind(addr: VsockAddr) -> io::Result<Async<VsockListener>> {
+        Async::new_nonblocking(VsockListener::bind(addr)?)
+    }
+
+    /// Accepts a new incoming connection.
+    ///
+    /// Returns the connection and the address of the peer.
+    pub async fn accept(&self) -> io::Result<(Async<VsockStream>, VsockAddr)> {
+        let (stream, addr) = self.read_with(|io| io.accept()).await?;
+
+        Ok((Async::new_nonblocking(stream)?, addr))
+    }
+}
+
+impl Async<VsockStream> {
+    /// Connects to the provided address.
+    pub async fn connect(addr: VsockAddr) -> io::Result<Async<VsockStream>> {
+        let stream = Async::new_nonblocking(VsockStream::from(socket()?))?;
+
+        let addr = addr.to_raw();
+
+        // Safety: `sockaddr_vm` is a plain C structure without padding
+        let addr = unsafe {
+            core::slice::from_raw_parts(
+                &addr as *const _ as *const u8,
+                core::mem::size_of::<sys::sockaddr_vm>(),
+            )
+        };
+
+        stream.connect_raw(addr).await?;
+
+        Ok(stream)
+    }
+
+    /// Reads some bytes from the stream.
+    ///
+    /// Returns the number of bytes read, where `Ok(0)` means that the peer has closed the stream (EOF).
+    pub async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(|mut io| io.read(buf)).await
+    }
+
+    /// Writes some bytes to the stream.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(|mut io| io.write(buf)).await
+    }
+}
+
+/// Creates a non-blocking vsock stream socket.
+fn socket() -> io::Result<OwnedFd> {
+    let fd = syscall_los!(unsafe {
+        sys::socket(
+            sys::AF_VSOCK,
+            sys::SOCK_STREAM | sys::SOCK_NONBLOCK | sys::SOCK_CLOEXEC,
+            0,
+        )
+    })?;
+
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// Reads a vsock address with `getsockname` or `getpeername`.
+fn vsock_addr<F>(f: F) -> io::Result<VsockAddr>
+where
+    F: FnOnce(*mut sys::sockaddr, *mut sys::socklen_t) -> sys::c_int,
+{
+    let mut addr = core::mem::MaybeUninit::<sys::sockaddr_vm>::zeroed();
+    let mut len = core::mem::size_of::<sys::sockaddr_vm>() as sys::socklen_t;
+
+    syscall_los!(f(addr.as_mut_ptr() as *mut _, &mut len))?;
+
+    VsockAddr::from_raw(unsafe { addr.assume_init_ref() })
+}
@@ -0,0 +1,40 @@
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::sync::Mutex;
+
+use async_io_mini::{ReactorConfig, REACTOR};
+
+static CHANGES: Mutex<Vec<bool>> = Mutex::new(Vec::new());
+
+fn on_activity_change(active: bool) {
+    CHANGES.lock().unwrap().push(active);
+}
+
+#[test]
+fn activity_change() -> std::io::Result<()> {
+    let mut config = ReactorConfig::new();
+    config.on_activity_change = Some(on_activity_change);
+
+    assert!(REACTOR.start_with_config(config)?);
+
+    let first = UdpSocket::bind("127.0.0.1:0")?;
+    let second = UdpSocket::bind("127.0.0.1:0")?;
+
+    // Registrations are acknowledged only after the reactor has synced its poller
+    REACTOR.register(first.as_raw_fd())?;
+    assert_eq!(*CHANGES.lock().unwrap(), [true]);
+
+    // Further registrations and partial deregistrations do not change the activity
+    REACTOR.register(second.as_raw_fd())?;
+    REACTOR.deregister(first.as_raw_fd())?;
+    assert_eq!(*CHANGES.lock().unwrap(), [true]);
+
+    REACTOR.deregister(second.as_raw_fd())?;
+    assert_eq!(*CHANGES.lock().unwrap(), [true, false]);
+
+    REACTOR.register(first.as_raw_fd())?;
+    REACTOR.deregister(first.as_raw_fd())?;
+    assert_eq!(*CHANGES.lock().unwrap(), [true, false, true, false]);
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use core::future::Future;
 
 use std::io;
-use std::net::{Shutdown, TcpListener, TcpStream, UdpSocket};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
 use std::sync::Arc;
 use std::thread;
 
@@ -60,6 +60,153 @@ fn tcp_connect() -> io::Result<()> {
     })
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn tcp_accept_flags() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream = Async::<TcpStream>::connect(addr).await?;
+        let (accepted, peer) = listener.accept().await?;
+        assert_eq!(peer, stream.get_ref().local_addr()?);
+
+        let fd = accepted.as_raw_fd();
+        assert_ne!(
+            unsafe { libc::fcntl(fd, libc::F_GETFL) } & libc::O_NONBLOCK,
+            0
+        );
+        assert_ne!(
+            unsafe { libc::fcntl(fd, libc::F_GETFD) } & libc::FD_CLOEXEC,
+            0
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_accept_now() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        assert!(listener.accept_now()?.is_none());
+
+        let stream = Async::<TcpStream>::connect(addr).await?;
+
+        listener.readable().await?;
+
+        let (accepted, peer) = listener.accept_now()?.unwrap();
+        assert_eq!(peer, stream.get_ref().local_addr()?);
+        assert_eq!(accepted.get_ref().local_addr()?, addr);
+
+        assert!(listener.accept_now()?.is_none());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_listener_builder() -> io::Result<()> {
+    future::block_on(async {
+        let listener1 = Async::<TcpListener>::builder()
+            .reuse_address(true)
+            .reuse_port(true)
+            .backlog(4)
+            .bind(([127, 0, 0, 1], 0))?;
+        let addr = listener1.get_ref().local_addr()?;
+
+        // The listener does not leak into child processes
+        let flags =
+            unsafe { libc::fcntl(std::os::fd::AsRawFd::as_raw_fd(&listener1), libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+
+        // Thanks to `SO_REUSEPORT`, a second listener can be bound to the same address
+        let _listener2 = Async::<TcpListener>::builder()
+            .reuse_address(true)
+            .reuse_port(true)
+            .bind(addr)?;
+
+        let mut stream = Async::<TcpStream>::connect(addr).await?;
+        stream.write_all(LOREM_IPSUM).await?;
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn tcp_connect_raw() -> io::Result<()> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+        assert!(fd >= 0);
+
+        let stream = Async::new(TcpStream::from(unsafe { OwnedFd::from_raw_fd(fd) }))?;
+
+        let sockaddr = libc::sockaddr_in {
+            sin_family: libc::AF_INET as _,
+            sin_port: addr.port().to_be(),
+            sin_addr: libc::in_addr {
+                s_addr: u32::from_ne_bytes([127, 0, 0, 1]),
+            },
+            sin_zero: Default::default(),
+        };
+        let sockaddr = unsafe {
+            core::slice::from_raw_parts(
+                &sockaddr as *const _ as *const u8,
+                core::mem::size_of_val(&sockaddr),
+            )
+        };
+
+        stream.connect_raw(sockaddr).await?;
+        assert_eq!(stream.get_ref().peer_addr()?, addr);
+
+        // Now that the listener is closed, connect should fail.
+        drop(listener);
+
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+        let stream = Async::new(TcpStream::from(unsafe { OwnedFd::from_raw_fd(fd) }))?;
+
+        let err = stream.connect_raw(sockaddr).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_stream_builder() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream = Async::<TcpStream>::builder().connect(addr).await?;
+        assert!(stream.nodelay()?);
+
+        let stream = Async::<TcpStream>::builder()
+            .nodelay(false)
+            .connect(addr)
+            .await?;
+        assert!(!stream.nodelay()?);
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            stream.set_quickack(true)?;
+            assert!(stream.quickack()?);
+        }
+
+        Ok(())
+    })
+}
+
 #[test]
 fn tcp_peek_read() -> io::Result<()> {
     future::block_on(async {
@@ -83,175 +230,2195 @@ fn tcp_peek_read() -> io::Result<()> {
 }
 
 #[test]
-fn tcp_reader_hangup() -> io::Result<()> {
+fn tcp_read_uninit() -> io::Result<()> {
     future::block_on(async {
         let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
         let addr = listener.get_ref().local_addr()?;
-        let task = spawn(async move { listener.accept().await });
 
-        let mut stream2 = Async::<TcpStream>::connect(addr).await?;
-        let stream1 = task.await?.0;
+        let mut stream = Async::<TcpStream>::connect(addr).await?;
+        stream.write_all(LOREM_IPSUM).await?;
+        drop(stream);
 
-        let task = spawn(async move {
-            Timer::after(Duration::from_secs(1)).await;
-            drop(stream1);
-        });
+        let stream = listener.accept().await?.0;
 
-        while stream2.write_all(LOREM_IPSUM).await.is_ok() {}
-        task.await;
+        let mut buf = Vec::<u8>::with_capacity(4096);
+
+        loop {
+            let len = stream.read_uninit(buf.spare_capacity_mut()).await?;
+            if len == 0 {
+                break;
+            }
+
+            unsafe { buf.set_len(buf.len() + len) };
+        }
+
+        assert_eq!(buf, LOREM_IPSUM);
 
         Ok(())
     })
 }
 
 #[test]
-fn tcp_writer_hangup() -> io::Result<()> {
+fn tcp_read_to_end_with_limit() -> io::Result<()> {
     future::block_on(async {
         let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
         let addr = listener.get_ref().local_addr()?;
-        let task = spawn(async move { listener.accept().await });
 
-        let mut stream2 = Async::<TcpStream>::connect(addr).await?;
-        let stream1 = task.await?.0;
+        for (limit, ok) in [
+            (LOREM_IPSUM.len() * 2, true),
+            (LOREM_IPSUM.len(), true),
+            (LOREM_IPSUM.len() - 1, false),
+        ] {
+            let mut stream = Async::<TcpStream>::connect(addr).await?;
+            stream.write_all(LOREM_IPSUM).await?;
+            drop(stream);
 
-        let task = spawn(async move {
-            Timer::after(Duration::from_secs(1)).await;
-            drop(stream1);
-        });
+            let stream = listener.accept().await?.0;
 
-        let mut v = vec![];
-        stream2.read_to_end(&mut v).await?;
-        assert!(v.is_empty());
+            let mut buf = b"head".to_vec();
+            let result = stream.read_to_end_with_limit(&mut buf, limit).await;
+
+            if ok {
+                assert_eq!(result?, LOREM_IPSUM.len());
+                assert_eq!(&buf[4..], LOREM_IPSUM);
+            } else {
+                assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Other);
+                assert!(buf.len() > limit);
+            }
+
+            assert!(buf.starts_with(b"head"));
+        }
 
-        task.await;
         Ok(())
     })
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
 #[test]
-fn udp_send_recv() -> io::Result<()> {
+fn tcp_congestion() -> io::Result<()> {
     future::block_on(async {
-        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
-        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
-        socket1.get_ref().connect(socket2.get_ref().local_addr()?)?;
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
 
-        let mut buf = [0u8; 1024];
+        let stream = Async::<TcpStream>::connect(addr).await?;
 
-        socket1.send(LOREM_IPSUM).await?;
-        let n = socket2.peek(&mut buf).await?;
-        assert_eq!(&buf[..n], LOREM_IPSUM);
-        let n = socket2.recv(&mut buf).await?;
-        assert_eq!(&buf[..n], LOREM_IPSUM);
+        // Reno is always built into the kernel
+        stream.set_congestion("reno")?;
+        assert_eq!(stream.congestion()?, "reno");
 
-        socket2
-            .send_to(LOREM_IPSUM, socket1.get_ref().local_addr()?)
-            .await?;
-        let n = socket1.peek_from(&mut buf).await?.0;
-        assert_eq!(&buf[..n], LOREM_IPSUM);
-        let n = socket1.recv_from(&mut buf).await?.0;
-        assert_eq!(&buf[..n], LOREM_IPSUM);
+        assert_eq!(
+            stream.set_congestion("no-such-algo").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(stream.congestion()?, "reno");
 
         Ok(())
     })
 }
 
-// Test that we correctly re-register interests after we've previously been
-// interested in both readable and writable events and then we get only one of
-// those (we need to re-register interest on the other).
 #[test]
-fn tcp_duplex() -> io::Result<()> {
+fn tcp_poll_read_ready() -> io::Result<()> {
     future::block_on(async {
         let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
-        let stream1 =
-            Arc::new(Async::<TcpStream>::connect(listener.get_ref().local_addr()?).await?);
-        let stream2 = Arc::new(listener.accept().await?.0);
+        let addr = listener.get_ref().local_addr()?;
 
-        async fn do_read(s: Arc<Async<TcpStream>>) -> io::Result<()> {
-            let mut buf = vec![0u8; 4096];
-            loop {
-                let len = (&*s).read(&mut buf).await?;
-                if len == 0 {
-                    return Ok(());
-                }
-            }
-        }
+        let mut stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
 
-        async fn do_write(s: Arc<Async<TcpStream>>) -> io::Result<()> {
-            let buf = vec![0u8; 4096];
-            for _ in 0..4096 {
-                (&*s).write_all(&buf).await?;
-            }
-            s.get_ref().shutdown(Shutdown::Write)?;
-            Ok(())
+        stream1.write_all(LOREM_IPSUM).await?;
+
+        let ready = future::poll_fn(|cx| stream2.poll_read_ready(cx)).await?;
+        assert!(ready.is_readable());
+        assert!(!ready.is_read_closed());
+        assert!(!ready.is_write_closed());
+
+        // The readiness is only described; the data is still there
+        let mut buf = vec![0; LOREM_IPSUM.len()];
+        stream2.recv_exact(&mut buf).await?;
+        assert_eq!(buf, LOREM_IPSUM);
+
+        drop(stream1);
+
+        let ready = future::poll_fn(|cx| stream2.poll_read_ready(cx)).await?;
+        assert!(ready.is_readable());
+        assert!(ready.is_read_closed());
+        assert!(!ready.is_write_closed());
+
+        stream2.get_ref().shutdown(Shutdown::Write)?;
+
+        let ready = future::poll_fn(|cx| stream2.poll_read_ready(cx)).await?;
+        assert!(ready.is_write_closed());
+
+        // A reset connection reports its error, rather than a closed read half
+        let stream3 = Async::<TcpStream>::connect(addr).await?;
+        let stream4 = listener.accept().await?.0;
+
+        // Closing with a zero linger timeout resets the connection
+        let linger = libc::linger {
+            l_onoff: 1,
+            l_linger: 0,
+        };
+        assert_eq!(
+            unsafe {
+                libc::setsockopt(
+                    std::os::fd::AsRawFd::as_raw_fd(&stream3),
+                    libc::SOL_SOCKET,
+                    libc::SO_LINGER,
+                    &linger as *const _ as *const _,
+                    core::mem::size_of_val(&linger) as _,
+                )
+            },
+            0
+        );
+        drop(stream3);
+
+        let err = future::poll_fn(|cx| stream4.poll_read_ready(cx))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_read_header_body() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let mut stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
+
+        stream1.write_all(&[0, 5]).await?;
+        stream1.write_all(b"hellotrailer").await?;
+
+        let mut header = [0; 2];
+        let mut body = [0; 16];
+
+        // Give both writes the time to arrive
+        stream2.readable().await?;
+        Timer::after(Duration::from_millis(100)).await;
+
+        let (header_len, body_len) = stream2
+            .read_header_body(&mut header, &mut body, |header| {
+                u16::from_be_bytes([header[0], header[1]]) as usize
+            })
+            .await?;
+
+        assert_eq!(header_len, 2);
+        assert_eq!(&body[..body_len], b"hello");
+
+        // The rest of the stream is left alone
+        let mut rest = [0; 7];
+        stream2.recv_exact(&mut rest).await?;
+        assert_eq!(&rest, b"trailer");
+
+        drop(stream1);
+
+        assert_eq!(
+            stream2
+                .read_header_body(&mut header, &mut body, |_| unreachable!())
+                .await?,
+            (0, 0)
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_read_pooled() -> io::Result<()> {
+    use async_io_mini::BufferPool;
+
+    struct Pool(std::cell::RefCell<Vec<Vec<u8>>>);
+
+    impl BufferPool for Pool {
+        type Buf = Vec<u8>;
+
+        fn acquire(&self) -> Option<Vec<u8>> {
+            self.0.borrow_mut().pop()
         }
+    }
 
-        // Read from and write to stream1.
-        let r1 = spawn(do_read(stream1.clone()));
-        let w1 = spawn(do_write(stream1));
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
 
-        // Sleep a bit, so that reading and writing are both blocked.
-        Timer::after(Duration::from_millis(5)).await;
+        let mut stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
 
-        // Start reading stream2, make stream1 writable.
-        let r2 = spawn(do_read(stream2.clone()));
+        stream1.write_all(LOREM_IPSUM).await?;
 
-        // Finish writing to stream1.
-        w1.await?;
-        r2.await?;
+        let pool = Pool(std::cell::RefCell::new(vec![vec![0; 16]]));
 
-        // Start writing to stream2, make stream1 readable.
-        let w2 = spawn(do_write(stream2));
+        let buf = stream2.read_pooled(&pool).await?;
+        assert!(!buf.is_empty());
+        assert_eq!(&*buf, &LOREM_IPSUM[..buf.len()]);
 
-        // Will r1 be correctly woken?
-        r1.await?;
-        w2.await?;
+        let offset = buf.len();
+
+        // The only buffer is taken
+        assert_eq!(
+            stream2.read_pooled(&pool).await.unwrap_err().kind(),
+            io::ErrorKind::OutOfMemory
+        );
+
+        pool.0.borrow_mut().push(buf.into_inner());
+
+        let buf = stream2.read_pooled(&pool).await?;
+        assert_eq!(&*buf, &LOREM_IPSUM[offset..offset + buf.len()]);
 
         Ok(())
     })
 }
 
 #[test]
-fn shutdown() -> io::Result<()> {
+fn tcp_read_budgeted() -> io::Result<()> {
     future::block_on(async {
         let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
         let addr = listener.get_ref().local_addr()?;
-        let ((mut reader, _), writer) =
-            future::try_zip(listener.accept(), Async::<TcpStream>::connect(addr)).await?;
 
-        // The writer must be closed in order for `read_to_end()` to finish.
-        let mut buf = Vec::new();
-        future::try_zip(reader.read_to_end(&mut buf), async {
-            writer.get_ref().shutdown(Shutdown::Write)
-        })
-        .await?;
+        let mut stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
+
+        stream1.write_all(LOREM_IPSUM).await?;
+        stream2.readable().await?;
+
+        let mut budget = 2;
+        let mut buf = [0u8; 4];
+
+        // The data is there, so these do not wait
+        for _ in 0..2 {
+            assert_eq!(stream2.read_budgeted(&mut buf, &mut budget).await?, 4);
+        }
+
+        assert_eq!(budget, 0);
+
+        // The budget is exhausted, so the next read yields to the reactor first, even though the data is there
+        {
+            let mut read = std::pin::pin!(stream2.read_budgeted(&mut buf, &mut budget));
+            assert!(future::poll_once(&mut read).await.is_none());
+            assert_eq!(read.await?, 4);
+        }
+
+        assert_eq!(&buf, &LOREM_IPSUM[8..12]);
 
         Ok(())
     })
 }
 
-// prevent source from unregistering by trying to register it twice
 #[test]
-fn duplicate_socket_insert() -> io::Result<()> {
+fn tcp_try_clone() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
     future::block_on(async {
         let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
-        let addr = listener.as_ref().local_addr()?;
+        let addr = listener.get_ref().local_addr()?;
 
-        // attempt to register twice
-        assert!(Async::new(&listener).is_err(), "fails upon second insert");
+        let stream1 = Async::<TcpStream>::connect(addr).await?;
+        let mut stream2 = listener.accept().await?.0;
 
-        // Read and Write to confirm socket did not deregister on duplication attempt
-        // Write to stream_w
-        let mut stream_w = Async::<TcpStream>::connect(addr).await?;
-        stream_w.write(LOREM_IPSUM).await?;
-        stream_w.get_ref().shutdown(Shutdown::Write)?;
+        let mut clone = stream1.try_clone()?;
+        assert_ne!(clone.as_raw_fd(), stream1.as_raw_fd());
 
-        // Read from stream_r
-        let mut stream_r = listener.accept().await?.0;
-        let mut buffer = vec![0; LOREM_IPSUM.len()];
-        stream_r.read_exact(&mut buffer).await?;
+        // Dropping the original leaves the clone registered, and the connection open
+        drop(stream1);
 
-        assert_eq!(buffer, LOREM_IPSUM);
+        clone.write_all(LOREM_IPSUM).await?;
+
+        let mut buf = vec![0; LOREM_IPSUM.len()];
+        stream2.recv_exact(&mut buf).await?;
+        assert_eq!(buf, LOREM_IPSUM);
+
+        stream2.write_all(LOREM_IPSUM).await?;
+        clone.recv_exact(&mut buf).await?;
+        assert_eq!(buf, LOREM_IPSUM);
 
         Ok(())
     })
 }
+
+#[test]
+fn tcp_reader_hangup() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move { listener.accept().await });
+
+        let mut stream2 = Async::<TcpStream>::connect(addr).await?;
+        let stream1 = task.await?.0;
+
+        let task = spawn(async move {
+            Timer::after(Duration::from_secs(1)).await;
+            drop(stream1);
+        });
+
+        while stream2.write_all(LOREM_IPSUM).await.is_ok() {}
+        task.await;
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_writer_hangup() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move { listener.accept().await });
+
+        let mut stream2 = Async::<TcpStream>::connect(addr).await?;
+        let stream1 = task.await?.0;
+
+        let task = spawn(async move {
+            Timer::after(Duration::from_secs(1)).await;
+            drop(stream1);
+        });
+
+        let mut v = vec![];
+        stream2.read_to_end(&mut v).await?;
+        assert!(v.is_empty());
+
+        task.await;
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_read_until_deadline() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move { listener.accept().await });
+
+        let mut stream2 = Async::<TcpStream>::connect(addr).await?;
+        let stream1 = task.await?.0;
+
+        let mut buf = [0u8; 1024];
+        let deadline = || std::time::Instant::now() + std::time::Duration::from_millis(100);
+
+        let err = stream1
+            .read_until_deadline(&mut buf, deadline())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        stream2.write_all(LOREM_IPSUM).await?;
+        let n = stream1.read_until_deadline(&mut buf, deadline()).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+
+        drop(stream2);
+        assert_eq!(stream1.read_until_deadline(&mut buf, deadline()).await?, 0);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_recv_exact() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move { listener.accept().await });
+
+        let mut stream2 = Async::<TcpStream>::connect(addr).await?;
+        let stream1 = task.await?.0;
+
+        let task = spawn(async move {
+            // Write the record in two parts, to exercise the short read path
+            let (head, tail) = LOREM_IPSUM.split_at(LOREM_IPSUM.len() / 2);
+
+            stream2.write_all(head).await?;
+            Timer::after(Duration::from_millis(100)).await;
+            stream2.write_all(tail).await?;
+
+            io::Result::Ok(())
+        });
+
+        let mut buf = vec![0; LOREM_IPSUM.len()];
+        stream1.recv_exact(&mut buf).await?;
+        assert_eq!(buf, LOREM_IPSUM);
+
+        task.await?;
+
+        // The writer is gone, so there is nothing more to read
+        let err = stream1.recv_exact(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_probe_liveness() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move { listener.accept().await });
+
+        let stream2 = Async::<TcpStream>::connect(addr).await?;
+        let stream1 = task.await?.0;
+
+        assert!(stream2.probe_liveness()?);
+
+        drop(stream1);
+
+        // Wait for the FIN to arrive
+        stream2.readable().await?;
+
+        assert!(!stream2.probe_liveness()?);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_write_all_vectored() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let task = spawn(async move {
+            let mut stream = listener.accept().await?.0;
+
+            let mut v = vec![];
+            stream.read_to_end(&mut v).await?;
+
+            io::Result::Ok(v)
+        });
+
+        let stream = Async::<TcpStream>::connect(addr).await?;
+
+        // Large enough to overflow the socket buffers and cause short writes
+        let header = LOREM_IPSUM.repeat(1024);
+        let payload = LOREM_IPSUM.repeat(16 * 1024);
+
+        stream
+            .write_all_vectored(&mut [
+                io::IoSlice::new(&[]),
+                io::IoSlice::new(&header),
+                io::IoSlice::new(&payload),
+            ])
+            .await?;
+        stream.get_ref().shutdown(Shutdown::Write)?;
+
+        let v = task.await?;
+        assert_eq!(v.len(), header.len() + payload.len());
+        assert_eq!(&v[..header.len()], header);
+        assert_eq!(&v[header.len()..], payload);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_copy_bidirectional() -> io::Result<()> {
+    use async_io_mini::copy_bidirectional;
+
+    future::block_on(async {
+        let proxy_listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let server_listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+
+        let mut client =
+            Async::<TcpStream>::connect(proxy_listener.get_ref().local_addr()?).await?;
+        let a = proxy_listener.accept().await?.0;
+
+        let b = Async::<TcpStream>::connect(server_listener.get_ref().local_addr()?).await?;
+        let mut server = server_listener.accept().await?.0;
+
+        let proxy = spawn(async move { copy_bidirectional(&a, &b).await });
+
+        let request = LOREM_IPSUM.repeat(64);
+        let response = LOREM_IPSUM.repeat(256);
+
+        client.write_all(&request).await?;
+        client.get_ref().shutdown(Shutdown::Write)?;
+
+        // The half-close of the client should be propagated to the server
+        let mut v = vec![];
+        server.read_to_end(&mut v).await?;
+        assert_eq!(v, request);
+
+        server.write_all(&response).await?;
+        drop(server);
+
+        let mut v = vec![];
+        client.read_to_end(&mut v).await?;
+        assert_eq!(v, response);
+
+        assert_eq!(proxy.await?, (request.len() as u64, response.len() as u64));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_send_recv() -> io::Result<()> {
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        socket1.get_ref().connect(socket2.get_ref().local_addr()?)?;
+
+        let mut buf = [0u8; 1024];
+
+        socket1.send(LOREM_IPSUM).await?;
+        let n = socket2.peek(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+        let n = socket2.recv(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+
+        socket2
+            .send_to(LOREM_IPSUM, socket1.get_ref().local_addr()?)
+            .await?;
+        let n = socket1.peek_from(&mut buf).await?.0;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+        let n = socket1.recv_from(&mut buf).await?.0;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_recv_from_into() -> io::Result<()> {
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        socket1
+            .send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)
+            .await?;
+
+        let mut buf = [0u8; 1024];
+        let mut addr = core::mem::MaybeUninit::<libc::sockaddr_storage>::uninit();
+        let (n, addr_len) = socket2.recv_from_into(&mut buf, &mut addr).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+        assert_eq!(addr_len as usize, core::mem::size_of::<libc::sockaddr_in>());
+
+        let addr = unsafe { &*(addr.as_ptr() as *const libc::sockaddr_in) };
+        assert_eq!(
+            u16::from_be(addr.sin_port),
+            socket1.get_ref().local_addr()?.port()
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_connection_refused() -> io::Result<()> {
+    future::block_on(async {
+        // Bind and drop a socket, so that its port is (very likely) closed
+        let closed = std::net::UdpSocket::bind("127.0.0.1:0")?.local_addr()?;
+
+        let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        socket.connect(closed)?;
+
+        socket.send(LOREM_IPSUM).await?;
+
+        let mut buf = [0u8; 1024];
+        let err = socket.recv(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_take_error() -> io::Result<()> {
+    future::block_on(async {
+        // Bind and drop a socket, so that its port is (very likely) closed
+        let closed = std::net::UdpSocket::bind("127.0.0.1:0")?.local_addr()?;
+
+        let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        socket.connect(closed)?;
+
+        socket.send(LOREM_IPSUM).await?;
+
+        // Wait for the ICMP error to arrive
+        socket.readable().await?;
+
+        let err = socket.take_error()?.unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        assert!(socket.take_error()?.is_none());
+
+        // The error was transient, so the socket is still usable
+        socket.send(LOREM_IPSUM).await?;
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_tos() -> io::Result<()> {
+    let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+    socket.set_tos(46 << 2)?;
+    assert_eq!(socket.tos()?, 46 << 2);
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn udp_recv_with_timestamp() -> io::Result<()> {
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        socket2.set_timestamping(true)?;
+
+        let before = std::time::SystemTime::now();
+
+        socket1
+            .send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)
+            .await?;
+
+        let mut buf = [0u8; 1024];
+        let (len, addr, received_at) = socket2.recv_with_timestamp(&mut buf).await?;
+
+        assert_eq!(&buf[..len], LOREM_IPSUM);
+        assert_eq!(addr, socket1.get_ref().local_addr()?);
+
+        // The realtime clock might be stepped, so only check that the timestamp is roughly right
+        let skew = received_at
+            .expect("the kernel timestamps the datagrams")
+            .duration_since(before)
+            .unwrap_or_else(|err| err.duration());
+        assert!(skew < std::time::Duration::from_secs(5));
+
+        // Without timestamping, no time is made up
+        socket2.set_timestamping(false)?;
+
+        socket1
+            .send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)
+            .await?;
+
+        let (_, _, received_at) = socket2.recv_with_timestamp(&mut buf).await?;
+        assert_eq!(received_at, None);
+
+        io::Result::Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn udp_recv_gro() -> io::Result<()> {
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        socket2.set_gro(true)?;
+        socket1.connect(socket2.get_ref().local_addr()?)?;
+
+        for _ in 0..4 {
+            socket1.send(&[7; 100]).await?;
+        }
+
+        let mut buf = vec![0u8; 65536];
+        let mut received = 0;
+
+        while received < 400 {
+            let (len, segment_size) = socket2.recv_gro(&mut buf).await?;
+
+            assert_eq!(segment_size, 100);
+            assert_eq!(len % 100, 0);
+            assert!(buf[..len].iter().all(|b| *b == 7));
+
+            received += len;
+        }
+
+        assert_eq!(received, 400);
+
+        io::Result::Ok(())
+    })
+}
+
+#[test]
+fn udp_bind_in_range() -> io::Result<()> {
+    // Occupy a port, leaving room for two more after it
+    let (taken, port) = loop {
+        let taken = UdpSocket::bind("127.0.0.1:0")?;
+        let port = taken.local_addr()?.port();
+
+        if port < u16::MAX - 2 {
+            break (taken, port);
+        }
+    };
+
+    match Async::<UdpSocket>::bind_in_range([127, 0, 0, 1], port..=port + 2) {
+        Ok(socket) => {
+            let bound = socket.get_ref().local_addr()?.port();
+            assert!(bound > port && bound <= port + 2);
+        }
+        // Another process might have grabbed the other ports in the meantime
+        Err(err) => assert_eq!(err.kind(), io::ErrorKind::AddrInUse),
+    }
+
+    assert_eq!(
+        Async::<UdpSocket>::bind_in_range([127, 0, 0, 1], port..=port)
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::AddrInUse
+    );
+
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = port + 1..=port;
+    assert_eq!(
+        Async::<UdpSocket>::bind_in_range([127, 0, 0, 1], empty)
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    drop(taken);
+
+    Ok(())
+}
+
+#[test]
+fn udp_broadcast() -> io::Result<()> {
+    let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+    assert!(!socket.broadcast()?);
+    socket.set_broadcast(true)?;
+    assert!(socket.broadcast()?);
+
+    Ok(())
+}
+
+#[test]
+fn udp_clear_readiness() -> io::Result<()> {
+    use async_io_mini::Event;
+
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        // Start waiting for readability, then give up
+        assert!(future::poll_once(socket2.readable()).await.is_none());
+        socket2.clear_readiness(Event::Read | Event::Write)?;
+
+        // The socket should still be usable afterwards
+        socket1
+            .send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)
+            .await?;
+
+        let mut buf = [0u8; 1024];
+        let n = socket2.recv(&mut buf).await?;
+        assert_eq!(&buf[..n], LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_swap_interest() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::task::Wake;
+
+    use async_io_mini::{Event, REACTOR};
+
+    struct Flag(std::sync::atomic::AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    let fd = socket.as_raw_fd();
+
+    let flag = Arc::new(Flag(std::sync::atomic::AtomicBool::new(false)));
+    let waker = std::task::Waker::from(flag.clone());
+
+    // An unconnected UDP socket is always writable, so the waker should fire
+    if !REACTOR.swap_interest(fd, Event::Read, Event::Write, &waker)? {
+        let start = std::time::Instant::now();
+
+        while !flag.0.load(std::sync::atomic::Ordering::SeqCst) {
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn udp_modify_batch() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::task::Wake;
+
+    use async_io_mini::{Event, InterestOp, REACTOR};
+
+    struct Flag(std::sync::atomic::AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+    let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+    let flag = Arc::new(Flag(std::sync::atomic::AtomicBool::new(false)));
+    let waker = std::task::Waker::from(flag.clone());
+
+    let ops = [
+        InterestOp::new(socket1.as_raw_fd(), Event::Read, &waker),
+        InterestOp::new(socket2.as_raw_fd(), Event::Read, &waker),
+    ];
+
+    assert_eq!(
+        REACTOR.modify_batch::<1>(&ops).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    // Nothing to read, so both wakers are armed
+    assert_eq!(REACTOR.modify_batch::<2>(&ops)?.as_slice(), &[false, false]);
+
+    socket1
+        .get_ref()
+        .send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)?;
+
+    let start = std::time::Instant::now();
+
+    while !flag.0.load(std::sync::atomic::Ordering::SeqCst) {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    // The readiness of the second socket was observed by the reactor, and is consumed now
+    assert_eq!(REACTOR.modify_batch::<2>(&ops[1..])?.as_slice(), &[true]);
+
+    Ok(())
+}
+
+#[test]
+fn udp_register_and_wait() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::task::Wake;
+
+    use async_io_mini::{Event, REACTOR};
+
+    struct Flag(std::sync::atomic::AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_nonblocking(true)?;
+    let fd = socket.as_raw_fd();
+
+    REACTOR.start()?;
+
+    let flag = Arc::new(Flag(std::sync::atomic::AtomicBool::new(false)));
+    let waker = std::task::Waker::from(flag.clone());
+
+    // Nothing is known about a fresh registration, so the waker is armed
+    assert!(!REACTOR.register_and_wait(fd, Event::Read, &waker)?);
+
+    // Registered already
+    assert_eq!(
+        REACTOR
+            .register_and_wait(fd, Event::Read, &waker)
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::AlreadyExists
+    );
+
+    sender.send_to(LOREM_IPSUM, socket.local_addr()?)?;
+
+    let start = std::time::Instant::now();
+
+    while !flag.0.load(std::sync::atomic::Ordering::SeqCst) {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    REACTOR.deregister(fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_ready_any() -> io::Result<()> {
+    use async_io_mini::{ready_any, Event};
+
+    future::block_on(async {
+        let sender = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        sender
+            .send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)
+            .await?;
+
+        let (index, events) = ready_any(&[
+            (&socket1, Event::Read.into()),
+            (&socket2, Event::Read.into()),
+        ])
+        .await?;
+        assert_eq!(index, 1);
+        assert_eq!(events, Event::Read);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn event_poll_flags() {
+    use async_io_mini::{EnumSet, Event};
+
+    assert_eq!(Event::Read.to_poll_flags(), libc::POLLIN);
+    assert_eq!(Event::Write.to_poll_flags(), libc::POLLOUT);
+
+    assert_eq!(Event::from_poll_revents(0), EnumSet::empty());
+    assert_eq!(Event::from_poll_revents(libc::POLLIN), Event::Read);
+    assert_eq!(Event::from_poll_revents(libc::POLLOUT), Event::Write);
+    assert_eq!(
+        Event::from_poll_revents(libc::POLLERR),
+        Event::Read | Event::Write
+    );
+
+    for event in EnumSet::<Event>::all() {
+        assert_eq!(Event::from_poll_revents(event.to_poll_flags()), event);
+    }
+}
+
+#[test]
+fn reactor_wake_all() -> io::Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::{Context, Wake, Waker};
+
+    use async_io_mini::REACTOR;
+
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let sockets = (0..3)
+        .map(|_| Async::<UdpSocket>::bind(([127, 0, 0, 1], 0)))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let flags = sockets
+        .iter()
+        .map(|socket| {
+            let flag = Arc::new(Flag(AtomicBool::new(false)));
+            let waker = Waker::from(flag.clone());
+
+            // Nothing has been sent, so the sockets are not readable and the wakers are stored
+            assert!(socket
+                .poll_readable(&mut Context::from_waker(&waker))
+                .is_pending());
+
+            flag
+        })
+        .collect::<Vec<_>>();
+
+    REACTOR.wake_all()?;
+
+    assert!(flags.iter().all(|flag| flag.0.load(Ordering::SeqCst)));
+
+    Ok(())
+}
+
+#[test]
+fn udp_callback() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    use async_io_mini::{Event, REACTOR};
+
+    let socket1 = UdpSocket::bind("127.0.0.1:0")?;
+    let socket2 = UdpSocket::bind("127.0.0.1:0")?;
+    socket2.set_nonblocking(true)?;
+
+    REACTOR.start()?;
+    REACTOR.register(socket2.as_raw_fd())?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let reader = socket2.try_clone()?;
+
+    REACTOR.register_callback(
+        socket2.as_raw_fd(),
+        Event::Read,
+        Box::new(move || {
+            let mut buf = [0u8; 1024];
+
+            while let Ok(n) = reader.recv(&mut buf) {
+                sender.send(buf[..n].to_vec()).unwrap();
+            }
+        }),
+    )?;
+
+    for _ in 0..2 {
+        socket1.send_to(LOREM_IPSUM, socket2.local_addr()?)?;
+
+        let received = receiver
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(received, LOREM_IPSUM);
+    }
+
+    REACTOR.deregister(socket2.as_raw_fd())?;
+
+    Ok(())
+}
+
+#[test]
+fn udp_callback_readable() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_io_mini::{Event, REACTOR};
+
+    let socket1 = UdpSocket::bind("127.0.0.1:0")?;
+    let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    {
+        let calls = calls.clone();
+
+        REACTOR.register_callback(
+            socket2.as_raw_fd(),
+            Event::Read,
+            Box::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let _ = sender.send(());
+            }),
+        )?;
+    }
+
+    // The task cannot take over the event from the callback, nor call it
+    let err = future::block_on(socket2.readable()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+    REACTOR.sync_blocking()?;
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    // ... and the callback stays armed
+    socket1.send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)?;
+
+    receiver
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn udp_oneshot_callback() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::time::Duration;
+
+    use async_io_mini::{Event, REACTOR};
+
+    let socket1 = UdpSocket::bind("127.0.0.1:0")?;
+    let socket2 = UdpSocket::bind("127.0.0.1:0")?;
+    socket2.set_nonblocking(true)?;
+
+    REACTOR.start()?;
+    REACTOR.register_oneshot(socket2.as_raw_fd())?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    // The callback does not drain the socket, so it would be called on each pass, if not for the oneshot
+    REACTOR.register_callback(
+        socket2.as_raw_fd(),
+        Event::Read,
+        Box::new(move || sender.send(()).unwrap()),
+    )?;
+
+    socket1.send_to(LOREM_IPSUM, socket2.local_addr()?)?;
+
+    receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    // Force a few more passes
+    for _ in 0..3 {
+        REACTOR.notify()?;
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert!(receiver.try_recv().is_err());
+
+    // Still readable, so re-arming reports it again
+    REACTOR.set_interest(socket2.as_raw_fd(), Event::Read.into())?;
+
+    receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    REACTOR.deregister(socket2.as_raw_fd())?;
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn vsock_echo() -> io::Result<()> {
+    use async_io_mini::{VsockAddr, VsockListener, VsockStream};
+
+    future::block_on(async {
+        // Needs the vsock loopback transport (the `vsock_loopback` module), which VMs and containers often lack
+        let listener = match Async::<VsockListener>::bind(VsockAddr::new(
+            libc::VMADDR_CID_LOCAL,
+            libc::VMADDR_PORT_ANY,
+        )) {
+            Ok(listener) => listener,
+            Err(err) => {
+                eprintln!("Skipping, no vsock loopback: {err}");
+                return Ok(());
+            }
+        };
+
+        let addr = listener.get_ref().local_addr()?;
+        assert_eq!(addr.cid(), libc::VMADDR_CID_LOCAL);
+
+        let task = spawn(async move {
+            let (stream, _) = listener.accept().await?;
+
+            let mut buf = [0u8; 1024];
+            let len = stream.read(&mut buf).await?;
+            stream.write(&buf[..len]).await?;
+
+            io::Result::Ok(())
+        });
+
+        let stream = Async::<VsockStream>::connect(addr).await?;
+        stream.write(LOREM_IPSUM).await?;
+
+        let mut buf = [0u8; 1024];
+        let len = stream.read(&mut buf).await?;
+        assert_eq!(&buf[..len], LOREM_IPSUM);
+
+        task.await
+    })
+}
+
+// Test that we correctly re-register interests after we've previously been
+// interested in both readable and writable events and then we get only one of
+// those (we need to re-register interest on the other).
+#[test]
+fn tcp_duplex() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let stream1 =
+            Arc::new(Async::<TcpStream>::connect(listener.get_ref().local_addr()?).await?);
+        let stream2 = Arc::new(listener.accept().await?.0);
+
+        async fn do_read(s: Arc<Async<TcpStream>>) -> io::Result<()> {
+            let mut buf = vec![0u8; 4096];
+            loop {
+                let len = (&*s).read(&mut buf).await?;
+                if len == 0 {
+                    return Ok(());
+                }
+            }
+        }
+
+        async fn do_write(s: Arc<Async<TcpStream>>) -> io::Result<()> {
+            let buf = vec![0u8; 4096];
+            for _ in 0..4096 {
+                (&*s).write_all(&buf).await?;
+            }
+            s.get_ref().shutdown(Shutdown::Write)?;
+            Ok(())
+        }
+
+        // Read from and write to stream1.
+        let r1 = spawn(do_read(stream1.clone()));
+        let w1 = spawn(do_write(stream1));
+
+        // Sleep a bit, so that reading and writing are both blocked.
+        Timer::after(Duration::from_millis(5)).await;
+
+        // Start reading stream2, make stream1 writable.
+        let r2 = spawn(do_read(stream2.clone()));
+
+        // Finish writing to stream1.
+        w1.await?;
+        r2.await?;
+
+        // Start writing to stream2, make stream1 readable.
+        let w2 = spawn(do_write(stream2));
+
+        // Will r1 be correctly woken?
+        r1.await?;
+        w2.await?;
+
+        Ok(())
+    })
+}
+
+#[test]
+fn shutdown() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+        let ((mut reader, _), writer) =
+            future::try_zip(listener.accept(), Async::<TcpStream>::connect(addr)).await?;
+
+        // The writer must be closed in order for `read_to_end()` to finish.
+        let mut buf = Vec::new();
+        future::try_zip(reader.read_to_end(&mut buf), async {
+            writer.get_ref().shutdown(Shutdown::Write)
+        })
+        .await?;
+
+        Ok(())
+    })
+}
+
+// prevent source from unregistering by trying to register it twice
+#[test]
+fn duplicate_socket_insert() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.as_ref().local_addr()?;
+
+        // attempt to register twice
+        assert!(Async::new(&listener).is_err(), "fails upon second insert");
+
+        // Read and Write to confirm socket did not deregister on duplication attempt
+        // Write to stream_w
+        let mut stream_w = Async::<TcpStream>::connect(addr).await?;
+        stream_w.write(LOREM_IPSUM).await?;
+        stream_w.get_ref().shutdown(Shutdown::Write)?;
+
+        // Read from stream_r
+        let mut stream_r = listener.accept().await?.0;
+        let mut buffer = vec![0; LOREM_IPSUM.len()];
+        stream_r.read_exact(&mut buffer).await?;
+
+        assert_eq!(buffer, LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn tty_read_write() -> io::Result<()> {
+    use std::ffi::CStr;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    use async_io_mini::Tty;
+
+    future::block_on(async {
+        let master = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+        assert!(master >= 0);
+        let master = Tty::from(unsafe { OwnedFd::from_raw_fd(master) });
+
+        assert_eq!(unsafe { libc::grantpt(master.as_raw_fd()) }, 0);
+        assert_eq!(unsafe { libc::unlockpt(master.as_raw_fd()) }, 0);
+
+        let path = unsafe { CStr::from_ptr(libc::ptsname(master.as_raw_fd())) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        let master = Async::new(master)?;
+        let slave = Async::<Tty>::open(path)?;
+
+        slave.get_ref().set_raw()?;
+        slave.get_ref().set_baud_rate(115200)?;
+
+        let mut buf = vec![0; LOREM_IPSUM.len()];
+        let mut len = 0;
+
+        master.write(LOREM_IPSUM).await?;
+
+        while len < buf.len() {
+            len += slave.read(&mut buf[len..]).await?;
+        }
+
+        assert_eq!(buf, LOREM_IPSUM);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn reactor_health() -> io::Result<()> {
+    use async_io_mini::REACTOR;
+
+    future::block_on(async {
+        let _socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        // The reactor is healthy, so the health watch should not resolve
+        assert!(future::poll_once(REACTOR.watch_health()).await.is_none());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn reactor_pass_hook() -> io::Result<()> {
+    use async_io_mini::REACTOR;
+
+    REACTOR.start()?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    REACTOR.set_pass_hook(Some(Box::new(move || {
+        sender.send(()).ok();
+    })))?;
+
+    // The reactor thread might not be running yet, in which case the notification is a no-op
+    let result = (0..50).find_map(|_| {
+        REACTOR.notify().ok()?;
+
+        receiver
+            .recv_timeout(std::time::Duration::from_millis(100))
+            .ok()
+    });
+
+    REACTOR.set_pass_hook(None)?;
+
+    assert!(result.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn udp_two_readers() -> io::Result<()> {
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Arc::new(Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?);
+
+        let readers = (0..2)
+            .map(|_| {
+                let socket2 = socket2.clone();
+
+                spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    socket2.recv(&mut buf).await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Give both readers a chance to start waiting on the shared file descriptor
+        Timer::after(Duration::from_millis(100)).await;
+
+        for _ in 0..2 {
+            socket1
+                .send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)
+                .await?;
+        }
+
+        // Both readers should be woken up eventually, i.e. there should be no lost wakeups
+        for reader in readers {
+            assert_eq!(reader.await?, LOREM_IPSUM.len());
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn signal_fd() -> io::Result<()> {
+    use async_io_mini::SignalFd;
+
+    future::block_on(async {
+        let signals = Async::<SignalFd>::with_signals(&[libc::SIGUSR1])?;
+
+        // `raise` sends the signal to the calling thread, which has it blocked
+        assert_eq!(unsafe { libc::raise(libc::SIGUSR1) }, 0);
+
+        let signal = signals.next_signal().await?;
+        assert_eq!(signal.signo, libc::SIGUSR1);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn reactor_ready_deadline() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    use async_io_mini::{Event, REACTOR};
+
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        let fd = socket2.as_raw_fd();
+
+        // Nothing to read, so the deadline should pass first
+        let start = Instant::now();
+        let deadline = start + std::time::Duration::from_millis(100);
+
+        let err = REACTOR
+            .ready(fd, Event::Read.into(), Some(deadline))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(Instant::now() >= deadline);
+
+        socket1
+            .send_to(LOREM_IPSUM, socket2.get_ref().local_addr()?)
+            .await?;
+
+        let ready = REACTOR
+            .ready(
+                fd,
+                Event::Read.into(),
+                Some(Instant::now() + std::time::Duration::from_secs(10)),
+            )
+            .await?;
+        assert_eq!(ready, Event::Read);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn recycled_fd_registration() -> io::Result<()> {
+    use core::pin::pin;
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use core::task::{Context, Poll, Waker};
+
+    use std::os::fd::AsRawFd;
+    use std::task::Wake;
+
+    use async_io_mini::{Event, REACTOR};
+
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    // Start the reactor
+    let sender = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+    let old = UdpSocket::bind(("127.0.0.1", 0))?;
+    let new = UdpSocket::bind(("127.0.0.1", 0))?;
+
+    let fd = old.as_raw_fd();
+
+    REACTOR.register(fd)?;
+
+    // Registering the same file descriptor twice is an error
+    assert_eq!(
+        REACTOR.register(fd).unwrap_err().kind(),
+        io::ErrorKind::AlreadyExists
+    );
+
+    let old_flag = Arc::new(Flag(AtomicBool::new(false)));
+    let old_waker = Waker::from(old_flag.clone());
+
+    let mut old_ready = pin!(REACTOR.ready(fd, Event::Read.into(), None));
+    assert!(old_ready
+        .as_mut()
+        .poll(&mut Context::from_waker(&old_waker))
+        .is_pending());
+
+    // Close the old socket without deregistering it, and atomically recycle its file descriptor number
+    assert_eq!(unsafe { libc::dup2(new.as_raw_fd(), fd) }, fd);
+
+    // The stale registration is reclaimed
+    REACTOR.register(fd)?;
+
+    let new_flag = Arc::new(Flag(AtomicBool::new(false)));
+    let new_waker = Waker::from(new_flag.clone());
+
+    let mut new_ready = pin!(REACTOR.ready(fd, Event::Read.into(), None));
+    assert!(new_ready
+        .as_mut()
+        .poll(&mut Context::from_waker(&new_waker))
+        .is_pending());
+
+    future::block_on(sender.send_to(LOREM_IPSUM, new.local_addr()?))?;
+
+    while !new_flag.0.load(Ordering::SeqCst) {
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    assert!(matches!(
+        new_ready
+            .as_mut()
+            .poll(&mut Context::from_waker(&new_waker)),
+        Poll::Ready(Ok(ready)) if ready == Event::Read
+    ));
+
+    // The task waiting on the old socket must not be woken by the readiness of the new one
+    assert!(!old_flag.0.load(Ordering::SeqCst));
+
+    REACTOR.deregister(fd)?;
+
+    Ok(())
+}
+
+#[test]
+fn reactor_sync() -> io::Result<()> {
+    use async_io_mini::REACTOR;
+
+    future::block_on(async {
+        let _socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        REACTOR.sync().await?;
+        REACTOR.sync_blocking()?;
+
+        // Each call waits for its own pass
+        REACTOR.sync().await?;
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_read_cancellable() -> io::Result<()> {
+    use async_io_mini::CancelToken;
+
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
+
+        let token = CancelToken::new();
+
+        let canceller = token.clone();
+        let cancel = spawn(async move {
+            Timer::after(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+
+        // Nothing to read, so the read should be cancelled
+        let mut buf = [0u8; 1024];
+        let err = stream2
+            .read_cancellable(&mut buf, &token)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+
+        cancel.await;
+
+        // A cancelled token does not let any I/O through
+        let err = stream1
+            .write_cancellable(LOREM_IPSUM, &token)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+
+        let token = CancelToken::new();
+
+        let len = stream1.write_cancellable(LOREM_IPSUM, &token).await?;
+        assert_eq!(stream2.read_cancellable(&mut buf, &token).await?, len);
+        assert!(!token.is_cancelled());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_recv_into_ring() -> io::Result<()> {
+    use async_io_mini::RingBuffer;
+
+    struct Ring {
+        buf: [u8; 16],
+        head: usize,
+        len: usize,
+    }
+
+    impl RingBuffer for Ring {
+        fn free_segments(&mut self) -> (&mut [u8], &mut [u8]) {
+            let tail = (self.head + self.len) % self.buf.len();
+            let free = self.buf.len() - self.len;
+
+            let (start, end) = self.buf.split_at_mut(tail);
+            let first = free.min(end.len());
+
+            (&mut end[..first], &mut start[..free - first])
+        }
+
+        fn commit(&mut self, len: usize) {
+            self.len += len;
+        }
+    }
+
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        // The free region wraps around the end of the storage
+        let mut ring = Ring {
+            buf: [0; 16],
+            head: 10,
+            len: 0,
+        };
+
+        socket1
+            .send_to(b"0123456789ab", socket2.get_ref().local_addr()?)
+            .await?;
+
+        assert_eq!(socket2.recv_into_ring(&mut ring).await?, 12);
+        assert_eq!(ring.len, 12);
+        assert_eq!(&ring.buf[10..], b"012345");
+        assert_eq!(&ring.buf[..6], b"6789ab");
+
+        // A datagram longer than the free region is not committed partially
+        socket1
+            .send_to(b"0123456789", socket2.get_ref().local_addr()?)
+            .await?;
+
+        assert_eq!(
+            socket2.recv_into_ring(&mut ring).await.unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(ring.len, 12);
+
+        // A zero-length datagram is told apart from a full ring buffer
+        socket1
+            .send_to(b"", socket2.get_ref().local_addr()?)
+            .await?;
+        assert_eq!(socket2.recv_into_ring(&mut ring).await?, 0);
+
+        ring.len = 16;
+
+        assert_eq!(
+            socket2.recv_into_ring(&mut ring).await.unwrap_err().kind(),
+            io::ErrorKind::OutOfMemory
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn reactor_yield_now() -> io::Result<()> {
+    use async_io_mini::REACTOR;
+
+    future::block_on(async {
+        let _socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        // Without the reactor waking the task, `block_on` would hang here
+        for _ in 0..3 {
+            REACTOR.yield_now().await?;
+        }
+
+        Ok(())
+    })
+}
+
+#[test]
+fn reactor_next_deadline() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    use async_io_mini::{Event, REACTOR};
+
+    future::block_on(async {
+        let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        let deadline = Instant::now() + std::time::Duration::from_secs(3600);
+
+        // The deadline is removed once the future is dropped, so keep it alive
+        let mut ready =
+            core::pin::pin!(REACTOR.ready(socket.as_raw_fd(), Event::Read.into(), Some(deadline)));
+        assert!(future::poll_once(ready.as_mut()).await.is_none());
+
+        // Other tests might have armed earlier deadlines in the meantime
+        assert!(REACTOR
+            .next_deadline()
+            .is_some_and(|next_deadline| next_deadline <= deadline));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_recv_from_with_trunc() -> io::Result<()> {
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        let addr2 = socket2.get_ref().local_addr()?;
+
+        socket1.send_to(LOREM_IPSUM, addr2).await?;
+        socket1.send_to(&LOREM_IPSUM[..8], addr2).await?;
+
+        let mut buf = [0u8; 16];
+
+        let (len, addr, truncated) = socket2.recv_from_with_trunc(&mut buf).await?;
+        assert!(truncated);
+        assert_eq!(addr, socket1.get_ref().local_addr()?);
+        assert_eq!(buf, LOREM_IPSUM[..16]);
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(len, LOREM_IPSUM.len());
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        assert_eq!(len, buf.len());
+
+        let (len, _, truncated) = socket2.recv_from_with_trunc(&mut buf).await?;
+        assert!(!truncated);
+        assert_eq!(len, 8);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_connect_any() -> io::Result<()> {
+    future::block_on(async {
+        let timeout = std::time::Duration::from_secs(5);
+
+        let err = Async::<TcpStream>::connect_any(&[], timeout)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        // Nothing listens on the port of a dropped listener, so the first attempt is refused
+        let closed = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+
+        let err = Async::<TcpStream>::connect_any(&[closed], timeout)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+
+        let stream = Async::<TcpStream>::connect_any(&[closed, addr], timeout).await?;
+        assert_eq!(stream.get_ref().peer_addr()?, addr);
+
+        // An unrepresentable timeout means no timeout, rather than a panic
+        let stream = Async::<TcpStream>::connect_any(&[addr], std::time::Duration::MAX).await?;
+        assert_eq!(stream.get_ref().peer_addr()?, addr);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_short_writes() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    fn set_tiny_sndbuf(stream: &Async<TcpStream>) -> io::Result<()> {
+        let size: libc::c_int = 1024;
+
+        let result = unsafe {
+            libc::setsockopt(
+                stream.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_SNDBUF,
+                &size as *const _ as *const _,
+                core::mem::size_of_val(&size) as _,
+            )
+        };
+
+        if result < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    // Large enough to never fit in the send buffer, so that writes are short
+    let payload = (0..256 * 1024).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        for vectored in [false, true] {
+            let mut stream1 = Async::<TcpStream>::connect(addr).await?;
+            let mut stream2 = listener.accept().await?.0;
+
+            set_tiny_sndbuf(&stream1)?;
+
+            let reader = spawn(async move {
+                let mut received = Vec::new();
+                stream2.read_to_end(&mut received).await?;
+
+                io::Result::Ok(received)
+            });
+
+            if vectored {
+                let (head, tail) = payload.split_at(1000);
+
+                stream1
+                    .write_all_vectored(&mut [io::IoSlice::new(head), io::IoSlice::new(tail)])
+                    .await?;
+            } else {
+                stream1.write_all(&payload).await?;
+            }
+
+            stream1.get_ref().shutdown(Shutdown::Write)?;
+
+            // The remainders of the short writes must have been retried, rather than dropped
+            assert!(reader.await? == payload);
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn udp_recv_error() -> io::Result<()> {
+    future::block_on(async {
+        let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        socket.set_recverr(true)?;
+
+        assert!(socket.recv_error()?.is_none());
+
+        // Nothing listens on the port of a dropped socket, so the datagram triggers an ICMP "port unreachable"
+        let closed = UdpSocket::bind("127.0.0.1:0")?.local_addr()?;
+
+        socket.send_to(LOREM_IPSUM, closed).await?;
+
+        let err = loop {
+            if let Some(err) = socket.recv_error()? {
+                break err;
+            }
+
+            socket.readable().await?;
+        };
+
+        assert_eq!(err.errno, libc::ECONNREFUSED);
+        assert_eq!(err.origin, libc::SO_EE_ORIGIN_ICMP);
+        assert_eq!(err.destination, Some(closed));
+        assert_eq!(err.error().kind(), io::ErrorKind::ConnectionRefused);
+
+        assert!(socket.recv_error()?.is_none());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_writable_or_error() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
+
+        stream1.writable_or_error().await?;
+
+        // Closing with a zero linger timeout resets the connection
+        let linger = libc::linger {
+            l_onoff: 1,
+            l_linger: 0,
+        };
+        assert_eq!(
+            unsafe {
+                libc::setsockopt(
+                    std::os::fd::AsRawFd::as_raw_fd(&stream2),
+                    libc::SOL_SOCKET,
+                    libc::SO_LINGER,
+                    &linger as *const _ as *const _,
+                    core::mem::size_of_val(&linger) as _,
+                )
+            },
+            0
+        );
+        drop(stream2);
+
+        let err = loop {
+            match stream1.readable_or_error().await {
+                // A stale readiness from before the reset
+                Ok(()) => Timer::after(Duration::from_millis(10)).await,
+                Err(err) => break err,
+            }
+        };
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn tcp_corked() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    fn corked(stream: &Async<TcpStream>) -> io::Result<bool> {
+        let mut cork: libc::c_int = 0;
+        let mut len = core::mem::size_of_val(&cork) as libc::socklen_t;
+
+        let result = unsafe {
+            libc::getsockopt(
+                stream.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_CORK,
+                &mut cork as *mut _ as *mut _,
+                &mut len,
+            )
+        };
+
+        if result < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(cork != 0)
+        }
+    }
+
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
+
+        stream1
+            .corked(|mut stream| async move {
+                assert!(corked(stream)?);
+
+                stream.write_all(b"header").await?;
+                stream.write_all(b"body").await
+            })
+            .await?;
+
+        assert!(!corked(&stream1)?);
+
+        let mut buf = [0; 10];
+        stream2.recv_exact(&mut buf).await?;
+        assert_eq!(&buf, b"headerbody");
+
+        // The stream is uncorked on early return too
+        let result = stream1
+            .corked(|_| async { io::Result::<()>::Err(io::ErrorKind::Other.into()) })
+            .await;
+
+        assert!(result.is_err());
+        assert!(!corked(&stream1)?);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn oneshot() -> io::Result<()> {
+    future::block_on(async {
+        // Make sure the wakeup is routed through a running reactor
+        async_io_mini::REACTOR.start()?;
+
+        let (sender, receiver) = async_io_mini::oneshot();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(100));
+            sender.send(42).unwrap();
+        });
+
+        assert_eq!(receiver.await?, 42);
+        handle.join().unwrap();
+
+        // Dropping the sender without sending fails the receiver
+        let (sender, receiver) = async_io_mini::oneshot::<u32>();
+        drop(sender);
+        assert_eq!(
+            receiver.await.unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+
+        // Sending to a dropped receiver returns the value back
+        let (sender, receiver) = async_io_mini::oneshot();
+        drop(receiver);
+        assert!(sender.is_closed());
+        assert_eq!(sender.send(42), Err(42));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn drop_deregisters() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
+
+        let fd = stream1.as_raw_fd();
+
+        // Arm the registration, so that the reactor actively watches the file descriptor when it is dropped
+        {
+            let mut readable = core::pin::pin!(stream1.readable());
+            assert!(future::poll_once(&mut readable).await.is_none());
+        }
+
+        drop(stream1);
+
+        assert_eq!(
+            async_io_mini::REACTOR.user_data(fd).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        // A closed file descriptor in the `select` sets would have failed the reactor with `EBADF`
+        async_io_mini::REACTOR.sync().await?;
+
+        drop(stream2);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn udp_recv_batch() -> io::Result<()> {
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        let addr1 = socket1.get_ref().local_addr()?;
+        let addr2 = socket2.get_ref().local_addr()?;
+
+        // More datagrams than fit in a single `recvmmsg` call
+        for i in 0..20u8 {
+            socket1.send_to(&[i; 3], addr2).await?;
+        }
+
+        let mut storage = [[0u8; 8]; 32];
+        let mut bufs = storage.each_mut().map(|buf| buf.as_mut_slice());
+        let mut meta = [(0, SocketAddr::from(([0, 0, 0, 0], 0))); 32];
+
+        let mut received = 0;
+
+        while received < 20 {
+            let count = socket2
+                .recv_batch(&mut bufs[received..], &mut meta[received..])
+                .await?;
+
+            assert!(count > 0);
+            received += count;
+        }
+
+        assert_eq!(received, 20);
+
+        for (i, (buf, (len, addr))) in bufs.iter().zip(&meta).take(received).enumerate() {
+            assert_eq!(&buf[..*len], &[i as u8; 3]);
+            assert_eq!(*addr, addr1);
+        }
+
+        assert_eq!(socket2.recv_batch(&mut [], &mut meta).await?, 0);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn buffer_sizes() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream = Async::<TcpStream>::connect(addr).await?;
+        let socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        stream.set_send_buffer_size(32 * 1024)?;
+        stream.set_recv_buffer_size(48 * 1024)?;
+        socket.set_send_buffer_size(32 * 1024)?;
+        socket.set_recv_buffer_size(48 * 1024)?;
+
+        // The OS might adjust the sizes (Linux doubles them), but should not shrink them below the requested ones
+        assert!(stream.send_buffer_size()? >= 32 * 1024);
+        assert!(stream.recv_buffer_size()? >= 48 * 1024);
+        assert!(socket.send_buffer_size()? >= 32 * 1024);
+        assert!(socket.recv_buffer_size()? >= 48 * 1024);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn tcp_write_owned() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream1 = Arc::new(Async::<TcpStream>::connect(addr).await?);
+        let stream2 = listener.accept().await?.0;
+
+        let writer = spawn({
+            let stream1 = stream1.clone();
+
+            async move {
+                let buf = stream1.write_all_owned(LOREM_IPSUM.to_vec()).await?;
+                assert_eq!(buf, LOREM_IPSUM);
+
+                let (len, buf) = stream1.write_owned(buf).await?;
+                assert!(len > 0);
+
+                io::Result::Ok((len, buf))
+            }
+        });
+
+        let (len, buf) = writer.await?;
+        assert_eq!(buf, LOREM_IPSUM);
+
+        let mut received = vec![0; LOREM_IPSUM.len() + len];
+        stream2.recv_exact(&mut received).await?;
+
+        assert_eq!(&received[..LOREM_IPSUM.len()], LOREM_IPSUM);
+        assert_eq!(&received[LOREM_IPSUM.len()..], &LOREM_IPSUM[..len]);
+
+        Ok(())
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn tcp_writable_at_least() -> io::Result<()> {
+    future::block_on(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let stream1 = Async::<TcpStream>::connect(addr).await?;
+        let stream2 = listener.accept().await?.0;
+
+        stream1.set_send_buffer_size(16 * 1024)?;
+
+        // The kernel reports the doubled size, half of which is for its bookkeeping
+        let size = stream1.send_buffer_size()? / 2;
+
+        // Nothing is queued yet
+        stream1.writable_at_least(size).await?;
+
+        let err = stream1.writable_at_least(size + 1).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // Fill the send buffer (and the receive buffer of the peer)
+        let chunk = vec![0; 4096];
+        let mut written = 0;
+        loop {
+            match io::Write::write(&mut stream1.get_ref(), &chunk) {
+                Ok(len) => written += len,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => Err(err)?,
+            }
+        }
+
+        let reader = spawn(async move {
+            Timer::after(Duration::from_millis(50)).await;
+
+            let mut buf = vec![0; written];
+            stream2.recv_exact(&mut buf).await?;
+
+            io::Result::Ok(())
+        });
+
+        stream1.writable_at_least(size / 2).await?;
+
+        reader.await
+    })
+}
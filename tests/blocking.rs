@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use async_io_mini::{set_blocking_threads, spawn_blocking};
+
+use futures_lite::future;
+
+#[test]
+fn spawn_blocking_results() {
+    future::block_on(async {
+        let caller = thread::current().id();
+
+        let worker = spawn_blocking(|| thread::current().id()).await;
+        assert_ne!(worker, caller);
+
+        assert_eq!(spawn_blocking(|| 6 * 7).await, 42);
+    })
+}
+
+#[test]
+fn spawn_blocking_queues() {
+    set_blocking_threads(2).unwrap();
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+
+    let jobs = (0..8)
+        .map(|_| {
+            let running = running.clone();
+            let max_running = max_running.clone();
+
+            spawn_blocking(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
+
+                thread::sleep(Duration::from_millis(20));
+
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    future::block_on(async {
+        for job in jobs {
+            job.await;
+        }
+    });
+
+    // The pool is bounded
+    assert!(max_running.load(Ordering::SeqCst) <= 2);
+}
+
+#[test]
+fn spawn_blocking_panic() {
+    let result = thread::spawn(|| future::block_on(spawn_blocking(|| panic!("boom")))).join();
+
+    assert!(result.is_err());
+
+    // The worker thread survives the panic
+    assert_eq!(future::block_on(spawn_blocking(|| 1)), 1);
+}
@@ -0,0 +1,51 @@
+use core::future::Future;
+use core::pin::pin;
+
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::sync::Arc;
+use std::task::{Context, Wake, Waker};
+use std::time::{Duration, Instant};
+
+use async_io_mini::{Event, REACTOR};
+
+struct Noop;
+
+impl Wake for Noop {
+    fn wake(self: Arc<Self>) {}
+}
+
+#[test]
+fn dropped_deadlines() -> std::io::Result<()> {
+    // Drive the reactor inline, so that its passes can be timed
+    REACTOR.poll_once(Some(Duration::ZERO))?;
+
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_nonblocking(true)?;
+
+    REACTOR.register(socket.as_raw_fd())?;
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+
+    for _ in 0..100 {
+        let deadline = Instant::now() + Duration::from_millis(20);
+
+        let mut ready = pin!(REACTOR.ready(socket.as_raw_fd(), Event::Read.into(), Some(deadline)));
+        assert!(ready.as_mut().poll(&mut cx).is_pending());
+    }
+
+    assert_eq!(REACTOR.next_deadline(), None);
+
+    // Consume the notification of the changes
+    REACTOR.poll_once(Some(Duration::ZERO))?;
+
+    // None of the abandoned deadlines cuts the pass short
+    let start = Instant::now();
+    assert_eq!(REACTOR.poll_once(Some(Duration::from_millis(200)))?, 0);
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    REACTOR.deregister(socket.as_raw_fd())?;
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Wake, Waker};
+use std::time::{Duration, Instant};
+
+use async_io_mini::{EnumSet, Event, REACTOR};
+
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn drain_ready() -> std::io::Result<()> {
+    REACTOR.start()?;
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+
+    let sockets = (0..2)
+        .map(|_| {
+            let socket = UdpSocket::bind("127.0.0.1:0")?;
+            socket.set_nonblocking(true)?;
+
+            Ok(socket)
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let flags = sockets
+        .iter()
+        .map(|socket| {
+            let flag = Arc::new(Flag(AtomicBool::new(false)));
+
+            assert!(!REACTOR.register_and_wait(
+                socket.as_raw_fd(),
+                Event::Read,
+                &Waker::from(flag.clone())
+            )?);
+
+            sender.send_to(b"ping", socket.local_addr()?)?;
+
+            Ok(flag)
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let start = Instant::now();
+
+    while !flags.iter().all(|flag| flag.0.load(Ordering::SeqCst)) {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        std::thread::yield_now();
+    }
+
+    // Only as many file descriptors as fit are drained
+    let mut first = heapless::Vec::<_, 1>::new();
+    assert_eq!(REACTOR.drain_ready(&mut first)?, 1);
+
+    let mut rest = heapless::Vec::<_, 4>::new();
+    assert_eq!(REACTOR.drain_ready(&mut rest)?, 1);
+
+    let mut drained = first.iter().chain(&rest).copied().collect::<Vec<_>>();
+    drained.sort();
+
+    let mut expected = sockets
+        .iter()
+        .map(|socket| (socket.as_raw_fd(), EnumSet::only(Event::Read)))
+        .collect::<Vec<_>>();
+    expected.sort();
+
+    assert_eq!(drained, expected);
+
+    // The readiness is consumed
+    rest.clear();
+    assert_eq!(REACTOR.drain_ready(&mut rest)?, 0);
+
+    for socket in &sockets {
+        REACTOR.deregister(socket.as_raw_fd())?;
+    }
+
+    Ok(())
+}
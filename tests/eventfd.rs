@@ -0,0 +1,39 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::net::UdpSocket;
+
+use async_io_mini::{Async, ReactorConfig, REACTOR};
+use futures_lite::future;
+
+#[test]
+fn semaphore_mode() -> std::io::Result<()> {
+    let mut config = ReactorConfig::new();
+    config.eventfd_initval = 3;
+    config.eventfd_flags = libc::EFD_SEMAPHORE;
+
+    assert!(REACTOR.start_with_config(config)?);
+
+    // Each pass consumes a single notification, so the reactor works through the backlog one pass at a time
+    for _ in 0..10 {
+        REACTOR.notify()?;
+    }
+
+    REACTOR.sync_blocking()?;
+
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        socket1
+            .send_to(b"ping", socket2.get_ref().local_addr()?)
+            .await?;
+
+        let mut buf = [0; 4];
+        let (len, addr) = socket2.recv_from(&mut buf).await?;
+
+        assert_eq!(&buf[..len], b"ping");
+        assert_eq!(addr, socket1.get_ref().local_addr()?);
+
+        Ok(())
+    })
+}
@@ -0,0 +1,45 @@
+use std::net::UdpSocket;
+use std::os::fd::{AsRawFd, RawFd};
+use std::time::Duration;
+
+use async_io_mini::REACTOR;
+
+fn readable(fd: RawFd) -> bool {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    assert!(unsafe { libc::poll(&mut pollfd, 1, 0) } >= 0);
+
+    pollfd.revents & libc::POLLIN != 0
+}
+
+#[test]
+fn notification_fd() -> std::io::Result<()> {
+    let fd = REACTOR.notification_fd()?;
+    assert_eq!(REACTOR.notification_fd()?, fd);
+
+    REACTOR.poll_once(Some(Duration::ZERO))?;
+    assert!(!readable(fd));
+
+    // A registration change needs a reactor pass
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_nonblocking(true)?;
+    REACTOR.register(socket.as_raw_fd())?;
+    assert!(readable(fd));
+
+    REACTOR.poll_once(Some(Duration::ZERO))?;
+    assert!(!readable(fd));
+
+    REACTOR.notify()?;
+    assert!(readable(fd));
+
+    REACTOR.poll_once(Some(Duration::ZERO))?;
+    assert!(!readable(fd));
+
+    REACTOR.deregister(socket.as_raw_fd())?;
+
+    Ok(())
+}
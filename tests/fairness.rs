@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use async_io_mini::{Event, ReactorConfig, REACTOR};
+
+#[test]
+fn max_wakes_per_pass() -> std::io::Result<()> {
+    let mut config = ReactorConfig::new();
+    config.max_wakes_per_pass = Some(1);
+
+    assert!(REACTOR.start_with_config(config)?);
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+
+    let receivers = (0..3)
+        .map(|_| {
+            let receiver = UdpSocket::bind("127.0.0.1:0")?;
+            receiver.set_nonblocking(true)?;
+
+            // Never drained, so the receivers stay readable
+            sender.send_to(b"ping", receiver.local_addr()?)?;
+
+            Ok(receiver)
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let (tx, rx) = mpsc::channel();
+
+    for (index, receiver) in receivers.iter().enumerate() {
+        let tx = tx.clone();
+
+        REACTOR.register(receiver.as_raw_fd())?;
+        REACTOR.register_callback(
+            receiver.as_raw_fd(),
+            Event::Read,
+            Box::new(move || {
+                tx.send(index).ok();
+            }),
+        )?;
+    }
+
+    // Even though only one callback is called per pass, all of them should get their turn
+    let mut called = HashSet::new();
+    let start = Instant::now();
+
+    while called.len() < receivers.len() {
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        if let Ok(index) = rx.recv_timeout(Duration::from_millis(100)) {
+            called.insert(index);
+        }
+    }
+
+    for receiver in &receivers {
+        REACTOR.deregister(receiver.as_raw_fd())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use async_io_mini::{Async, FramedRead, LengthPrefix};
+
+use futures_lite::{future, StreamExt};
+
+fn connect(
+    write: impl FnOnce(TcpStream) -> io::Result<()> + Send + 'static,
+) -> io::Result<Async<TcpStream>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    thread::spawn(move || write(listener.accept()?.0));
+
+    future::block_on(Async::<TcpStream>::connect(addr))
+}
+
+#[test]
+fn frames() -> io::Result<()> {
+    let stream = connect(|mut stream| {
+        stream.write_all(&[0, 5])?;
+        stream.write_all(b"hello")?;
+
+        // A frame split across several readiness edges, including the prefix itself
+        for chunk in [&[0][..], &[6, b'f'], b"ram", b"ed"] {
+            stream.write_all(chunk)?;
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        // An empty frame
+        stream.write_all(&[0, 0])
+    })?;
+
+    future::block_on(async {
+        let mut frames = FramedRead::new(stream, LengthPrefix::U16Be);
+
+        assert_eq!(frames.next().await.unwrap()?, b"hello");
+        assert_eq!(frames.next().await.unwrap()?, b"framed");
+        assert_eq!(frames.next().await.unwrap()?, b"");
+        assert!(frames.next().await.is_none());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn little_endian_u32() -> io::Result<()> {
+    let stream = connect(|mut stream| {
+        stream.write_all(&3u32.to_le_bytes())?;
+        stream.write_all(b"abc")
+    })?;
+
+    future::block_on(async {
+        let mut frames = FramedRead::new(stream, LengthPrefix::U32Le);
+
+        assert_eq!(frames.next().await.unwrap()?, b"abc");
+        assert!(frames.next().await.is_none());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn oversized_frame() -> io::Result<()> {
+    let stream = connect(|mut stream| {
+        stream.write_all(&u32::MAX.to_be_bytes())?;
+        stream.write_all(b"abc")
+    })?;
+
+    future::block_on(async {
+        let mut frames = FramedRead::new(stream, LengthPrefix::U32Be).max_frame_size(1024);
+
+        assert_eq!(
+            frames.next().await.unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert!(frames.next().await.is_none());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn truncated_frame() -> io::Result<()> {
+    let stream = connect(|mut stream| {
+        stream.write_all(&[0, 10])?;
+        stream.write_all(b"abc")
+    })?;
+
+    future::block_on(async {
+        let mut frames = FramedRead::new(stream, LengthPrefix::U16Be);
+
+        assert_eq!(
+            frames.next().await.unwrap().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        Ok(())
+    })
+}
@@ -0,0 +1,296 @@
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_io_mini::{Event, REACTOR};
+
+#[test]
+fn poll_once_counts_woken() -> std::io::Result<()> {
+    // Nothing is registered yet, so the pass can only time out
+    assert_eq!(REACTOR.poll_once(Some(Duration::from_millis(10)))?, 0);
+
+    let receiver = UdpSocket::bind("127.0.0.1:0")?;
+    receiver.set_nonblocking(true)?;
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+
+    let fired = Arc::new(AtomicUsize::new(0));
+
+    REACTOR.register(receiver.as_raw_fd())?;
+    REACTOR.register_callback(receiver.as_raw_fd(), Event::Read, {
+        let fired = fired.clone();
+        Box::new(move || {
+            fired.fetch_add(1, Ordering::SeqCst);
+        })
+    })?;
+
+    sender.send_to(b"ping", receiver.local_addr()?)?;
+
+    // The first pass might be woken by the notification of the registration changes only
+    let mut woken = 0;
+    while woken == 0 {
+        woken = REACTOR.poll_once(Some(Duration::from_secs(1)))?;
+    }
+
+    assert_eq!(woken, 1);
+    assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+    REACTOR.deregister(receiver.as_raw_fd())?;
+
+    Ok(())
+}
+
+#[test]
+fn local_reactor() -> std::io::Result<()> {
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use async_io_mini::LocalReactor;
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let reactor = LocalReactor::<8>::new();
+
+    let receiver = UdpSocket::bind("127.0.0.1:0")?;
+    receiver.set_nonblocking(true)?;
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+
+    reactor.register(receiver.as_raw_fd())?;
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+
+    assert!(reactor
+        .poll_ready(receiver.as_raw_fd(), Event::Read, &mut cx)
+        .is_pending());
+
+    sender.send_to(b"ping", receiver.local_addr()?)?;
+
+    assert_eq!(reactor.poll_once(Some(Duration::from_secs(5)))?, 1);
+
+    assert!(matches!(
+        reactor.poll_ready(receiver.as_raw_fd(), Event::Read, &mut cx),
+        Poll::Ready(Ok(()))
+    ));
+
+    // Nothing is armed anymore, so the pass can only time out
+    assert_eq!(reactor.poll_once(Some(Duration::from_millis(10)))?, 0);
+
+    reactor.deregister(receiver.as_raw_fd())?;
+
+    Ok(())
+}
+
+#[test]
+fn local_reactor_reserve() -> std::io::Result<()> {
+    use std::io::ErrorKind;
+
+    use async_io_mini::LocalReactor;
+
+    let reactor = LocalReactor::<2>::new();
+
+    let data = UdpSocket::bind("127.0.0.1:0")?;
+    let spike = UdpSocket::bind("127.0.0.1:0")?;
+    let control = UdpSocket::bind("127.0.0.1:0")?;
+
+    assert_eq!(
+        reactor.reserve(3).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+
+    reactor.reserve(1)?;
+
+    reactor.register(data.as_raw_fd())?;
+
+    // The only other slot is reserved
+    assert_eq!(
+        reactor.register(spike.as_raw_fd()).unwrap_err().kind(),
+        ErrorKind::OutOfMemory
+    );
+
+    reactor.register_reserved(control.as_raw_fd())?;
+
+    reactor.deregister(control.as_raw_fd())?;
+    reactor.deregister(data.as_raw_fd())?;
+
+    Ok(())
+}
+
+#[test]
+fn local_reactor_user_data() -> std::io::Result<()> {
+    use std::io::ErrorKind;
+
+    use async_io_mini::LocalReactor;
+
+    let reactor = LocalReactor::<4>::new();
+
+    let socket1 = UdpSocket::bind("127.0.0.1:0")?;
+    let socket2 = UdpSocket::bind("127.0.0.1:0")?;
+
+    reactor.register_with_data(socket1.as_raw_fd(), 42)?;
+    reactor.register(socket2.as_raw_fd())?;
+
+    assert_eq!(reactor.user_data(socket1.as_raw_fd())?, 42);
+    assert_eq!(reactor.user_data(socket2.as_raw_fd())?, 0);
+
+    reactor.deregister(socket1.as_raw_fd())?;
+
+    assert_eq!(
+        reactor.user_data(socket1.as_raw_fd()).unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    reactor.deregister(socket2.as_raw_fd())?;
+
+    Ok(())
+}
+
+#[test]
+fn local_reactor_register_with_readiness() -> std::io::Result<()> {
+    use std::task::{Context, Wake, Waker};
+
+    use async_io_mini::LocalReactor;
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let reactor = LocalReactor::<4>::new();
+
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_nonblocking(true)?;
+
+    reactor.register_with_readiness(socket.as_raw_fd(), Event::Write.into())?;
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+
+    // Ready without any reactor pass
+    assert!(reactor
+        .poll_ready(socket.as_raw_fd(), Event::Write, &mut cx)
+        .is_ready());
+
+    // The initial readiness is consumed, and does not extend to other events
+    assert!(reactor
+        .poll_ready(socket.as_raw_fd(), Event::Write, &mut cx)
+        .is_pending());
+    assert!(reactor
+        .poll_ready(socket.as_raw_fd(), Event::Read, &mut cx)
+        .is_pending());
+
+    reactor.deregister(socket.as_raw_fd())?;
+
+    Ok(())
+}
+
+#[test]
+fn local_reactor_poll_poller() -> std::io::Result<()> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use async_io_mini::{LocalReactor, PollPoller};
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let reactor = LocalReactor::<8, _>::with_poller(PollPoller::<8>::new());
+
+    let receiver = UdpSocket::bind("127.0.0.1:0")?;
+    receiver.set_nonblocking(true)?;
+
+    // Above FD_SETSIZE, which the `select` poller cannot watch
+    let fd = unsafe { libc::dup2(receiver.as_raw_fd(), 2000) };
+    assert_eq!(fd, 2000);
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+
+    reactor.register(fd.as_raw_fd())?;
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+
+    assert!(reactor
+        .poll_ready(fd.as_raw_fd(), Event::Read, &mut cx)
+        .is_pending());
+
+    sender.send_to(b"ping", receiver.local_addr()?)?;
+
+    assert_eq!(reactor.poll_once(Some(Duration::from_secs(5)))?, 1);
+
+    assert!(matches!(
+        reactor.poll_ready(fd.as_raw_fd(), Event::Read, &mut cx),
+        Poll::Ready(Ok(()))
+    ));
+
+    // Nothing is armed anymore, so the pass can only time out
+    assert_eq!(reactor.poll_once(Some(Duration::from_millis(10)))?, 0);
+
+    reactor.deregister(fd.as_raw_fd())?;
+
+    Ok(())
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+#[test]
+fn local_reactor_kqueue_poller() -> std::io::Result<()> {
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use async_io_mini::{KqueuePoller, LocalReactor};
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let reactor = LocalReactor::<8, _>::with_poller(KqueuePoller::<8>::new());
+
+    let receiver = UdpSocket::bind("127.0.0.1:0")?;
+    receiver.set_nonblocking(true)?;
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+
+    reactor.register(receiver.as_raw_fd())?;
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+
+    assert!(reactor
+        .poll_ready(receiver.as_raw_fd(), Event::Read, &mut cx)
+        .is_pending());
+
+    sender.send_to(b"ping", receiver.local_addr()?)?;
+
+    assert_eq!(reactor.poll_once(Some(Duration::from_secs(5)))?, 1);
+
+    assert!(matches!(
+        reactor.poll_ready(receiver.as_raw_fd(), Event::Read, &mut cx),
+        Poll::Ready(Ok(()))
+    ));
+
+    // Nothing is armed anymore, so the pass can only time out
+    assert_eq!(reactor.poll_once(Some(Duration::from_millis(10)))?, 0);
+
+    reactor.deregister(receiver.as_raw_fd())?;
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::io;
+use std::net::UdpSocket;
+use std::sync::Mutex;
+
+use async_io_mini::{Async, REACTOR};
+
+use futures_lite::future;
+
+// The tests observe the global reactor statistics, so they should not run concurrently
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[test]
+fn stats() -> io::Result<()> {
+    let _guard = LOCK.lock().unwrap();
+
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        let mut buf = [0u8; 16];
+
+        socket1
+            .send_to(b"hello", socket2.get_ref().local_addr()?)
+            .await?;
+        socket2.recv_from(&mut buf).await?;
+
+        let stats = REACTOR.stats();
+
+        assert!(stats.passes > 0);
+        assert!(stats.notify_latency_max >= stats.notify_latency_last);
+        assert!(stats.wake_latency_max >= stats.wake_latency_last);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn modify_hold_time() -> io::Result<()> {
+    let _guard = LOCK.lock().unwrap();
+
+    // Registering is a modification, which does a couple of syscalls under the lock
+    let _socket = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+    let stats = REACTOR.stats();
+
+    assert!(stats.modify_hold_last > std::time::Duration::ZERO);
+    assert!(stats.modify_hold_max >= stats.modify_hold_last);
+
+    Ok(())
+}
+
+#[test]
+fn write_after_register() -> io::Result<()> {
+    let _guard = LOCK.lock().unwrap();
+
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        let passes = REACTOR.stats().passes;
+
+        // A brand-new socket is writable, so writing to it should not need a reactor round-trip
+        socket1
+            .send_to(b"hello", socket2.get_ref().local_addr()?)
+            .await?;
+
+        assert_eq!(REACTOR.stats().passes, passes);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn max_fd_only_armed() -> io::Result<()> {
+    use core::pin::pin;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    let _guard = LOCK.lock().unwrap();
+
+    future::block_on(async {
+        // Move the socket to a high file descriptor, so that it is above any other registration and the eventfd
+        const HIGH_FD: i32 = 900;
+
+        let socket = UdpSocket::bind("127.0.0.1:0")?;
+
+        let fd = unsafe { libc::dup2(socket.as_raw_fd(), HIGH_FD) };
+        if fd < 0 {
+            Err(io::Error::last_os_error())?;
+        }
+
+        drop(socket);
+
+        let socket = Async::<UdpSocket>::try_from(unsafe { OwnedFd::from_raw_fd(fd) })?;
+
+        // Registered, but idle
+        REACTOR.sync().await?;
+        assert!(REACTOR
+            .stats()
+            .max_fd
+            .is_some_and(|max_fd| max_fd < HIGH_FD));
+
+        // Armed
+        let mut readable = pin!(socket.readable());
+        assert!(future::poll_once(&mut readable).await.is_none());
+
+        REACTOR.sync().await?;
+        assert_eq!(REACTOR.stats().max_fd, Some(HIGH_FD));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn wake_counts() -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let _guard = LOCK.lock().unwrap();
+
+    future::block_on(async {
+        let socket1 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+        let socket2 = Async::<UdpSocket>::bind(([127, 0, 0, 1], 0))?;
+
+        let counts = |fd| {
+            REACTOR
+                .wake_counts()
+                .into_iter()
+                .find(|(count_fd, _, _)| *count_fd == fd)
+                .map(|(_, reads, writes)| (reads, writes))
+        };
+
+        assert_eq!(counts(socket2.as_raw_fd()), Some((0, 0)));
+
+        let mut buf = [0u8; 16];
+
+        for _ in 0..3 {
+            // Wait for the datagram first, so that each one wakes the reader
+            let recv = socket2.recv_from(&mut buf);
+            let send = async {
+                REACTOR.sync().await?;
+
+                socket1
+                    .send_to(b"hello", socket2.get_ref().local_addr()?)
+                    .await
+            };
+
+            let (recv, send) = future::zip(recv, send).await;
+            recv?;
+            send?;
+        }
+
+        assert_eq!(counts(socket2.as_raw_fd()), Some((3, 0)));
+
+        Ok(())
+    })
+}
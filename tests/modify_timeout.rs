@@ -0,0 +1,51 @@
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::thread;
+use std::time::Duration;
+
+use async_io_mini::{Async, ReactorConfig, REACTOR};
+
+#[test]
+fn wedged_reactor() -> std::io::Result<()> {
+    // Stall the reactor after each notification for much longer than the modification timeout
+    let mut config = ReactorConfig::new();
+    config.notify_debounce = Some(Duration::from_millis(500));
+    config.modify_timeout = Some(Duration::from_millis(50));
+
+    assert!(REACTOR.start_with_config(config)?);
+
+    // Wait until the reactor has completed its first pass
+    while REACTOR.sync_blocking().is_err() {
+        thread::yield_now();
+    }
+
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_nonblocking(true)?;
+
+    let fd = socket.as_raw_fd();
+
+    assert_eq!(
+        Async::new_nonblocking(socket).unwrap_err().kind(),
+        std::io::ErrorKind::TimedOut
+    );
+
+    // The socket is closed by now, so its registration must have been undone
+    assert_eq!(
+        REACTOR.user_data(fd).unwrap_err().kind(),
+        std::io::ErrorKind::NotFound
+    );
+
+    // Both the registration and its undoing are acknowledged once the reactor makes progress again
+    while REACTOR.waiting_count() > 0 {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // The file descriptor number can be registered again
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_nonblocking(true)?;
+
+    let result = Async::new_nonblocking(socket);
+    assert!(!matches!(result, Err(ref err) if err.kind() == std::io::ErrorKind::AlreadyExists));
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use async_io_mini::{Event, REACTOR};
+
+fn open_fds() -> std::io::Result<usize> {
+    Ok(std::fs::read_dir("/proc/self/fd")?.count())
+}
+
+#[test]
+fn teardown_after_panic() -> std::io::Result<()> {
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_nonblocking(true)?;
+
+    let fds = open_fds()?;
+
+    // A thread of our own, as unwinding does not fit into the tiny stack of the reactor thread
+    let reactor = thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(|| REACTOR.run_blocking())?;
+
+    let start = Instant::now();
+
+    while REACTOR.register(socket.as_raw_fd()).is_err() {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    // The notification file descriptor
+    assert!(open_fds()? > fds);
+
+    REACTOR.register_callback(
+        socket.as_raw_fd(),
+        Event::Read,
+        Box::new(|| panic!("buggy callback")),
+    )?;
+
+    sender.send_to(b"hello", socket.local_addr()?)?;
+
+    assert!(reactor.join().is_err());
+
+    // The notification file descriptor is closed, rather than leaked
+    assert_eq!(open_fds()?, fds);
+
+    // The reactor reports its failure, rather than panicking on a poisoned lock
+    assert_eq!(
+        REACTOR.deregister(socket.as_raw_fd()).unwrap_err().kind(),
+        std::io::ErrorKind::Other
+    );
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use async_io_mini::{Event, REACTOR};
+
+#[test]
+fn run_on_own_thread() -> std::io::Result<()> {
+    thread::Builder::new()
+        .name("custom-reactor".into())
+        .spawn(|| REACTOR.run_blocking())?;
+
+    // Wait until the reactor is started, and has completed its first pass
+    while REACTOR.sync_blocking().is_err() {
+        thread::yield_now();
+    }
+
+    // The reactor is already started, so it cannot be run (or started) again
+    assert_eq!(
+        REACTOR.run_blocking().unwrap_err().kind(),
+        std::io::ErrorKind::AlreadyExists
+    );
+    assert!(!REACTOR.start()?);
+
+    let receiver = UdpSocket::bind("127.0.0.1:0")?;
+    receiver.set_nonblocking(true)?;
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+
+    let (tx, rx) = mpsc::channel();
+
+    REACTOR.register(receiver.as_raw_fd())?;
+    REACTOR.register_callback(
+        receiver.as_raw_fd(),
+        Event::Read,
+        Box::new(move || {
+            if thread::current().name() == Some("custom-reactor") {
+                tx.send(()).ok();
+            }
+        }),
+    )?;
+
+    sender.send_to(b"ping", receiver.local_addr()?)?;
+
+    // The callback is called on the thread running the reactor
+    rx.recv_timeout(Duration::from_secs(5))
+        .expect("callback not called on the custom reactor thread");
+
+    REACTOR.deregister(receiver.as_raw_fd())?;
+
+    Ok(())
+}
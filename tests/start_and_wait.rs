@@ -0,0 +1,23 @@
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+
+use async_io_mini::REACTOR;
+
+#[test]
+fn start_and_wait() -> std::io::Result<()> {
+    REACTOR.start_and_wait()?;
+
+    // The reactor is up and running, so nothing is waiting for it
+    assert_eq!(REACTOR.waiting_count(), 0);
+
+    // Already started, so this returns right away
+    REACTOR.start_and_wait()?;
+
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    socket.set_nonblocking(true)?;
+
+    REACTOR.register(socket.as_raw_fd())?;
+    REACTOR.deregister(socket.as_raw_fd())?;
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::net::UdpSocket;
+use std::os::fd::AsRawFd;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use async_io_mini::{Event, REACTOR};
+
+#[test]
+fn register_right_after_start() -> std::io::Result<()> {
+    let receiver = UdpSocket::bind("127.0.0.1:0")?;
+    receiver.set_nonblocking(true)?;
+
+    let sender = UdpSocket::bind("127.0.0.1:0")?;
+
+    // Register before the reactor thread had a chance to create its notification file descriptor
+    // and to enter `select`
+    assert!(REACTOR.start()?);
+    REACTOR.register(receiver.as_raw_fd())?;
+
+    let (tx, rx) = mpsc::channel();
+
+    REACTOR.register_callback(
+        receiver.as_raw_fd(),
+        Event::Read,
+        Box::new(move || {
+            tx.send(()).ok();
+        }),
+    )?;
+
+    sender.send_to(b"ping", receiver.local_addr()?)?;
+
+    assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+    REACTOR.deregister(receiver.as_raw_fd())?;
+
+    Ok(())
+}
@@ -2,9 +2,9 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use async_io_mini::Timer;
+use async_io_mini::{timeout_at_system, Timer};
 use futures_lite::{future, FutureExt, StreamExt};
 
 fn spawn<T: Send + 'static>(
@@ -97,3 +97,32 @@ fn set() {
         assert!(start.elapsed() < Duration::from_secs(10));
     });
 }
+
+#[test]
+fn timeout_at_system_deadline() {
+    future::block_on(async {
+        let start = Instant::now();
+
+        let result = timeout_at_system(
+            SystemTime::now() + Duration::from_millis(500),
+            future::pending::<()>(),
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= Duration::from_millis(500));
+
+        let result = timeout_at_system(
+            SystemTime::now() + Duration::from_secs(10),
+            Timer::after(Duration::from_millis(100)),
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        // A deadline in the past still gives the future a chance to complete
+        let result = timeout_at_system(SystemTime::UNIX_EPOCH, future::ready(42)).await;
+        assert_eq!(result.unwrap(), 42);
+    });
+}
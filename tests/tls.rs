@@ -0,0 +1,133 @@
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+
+use async_io_mini::{Async, TlsStream};
+
+use futures_lite::{future, AsyncReadExt, AsyncWriteExt};
+
+use rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection};
+
+// A CA and a `localhost` certificate issued by it, valid until 2126
+const CA: &[u8] = include_bytes!("tls/ca.der");
+const CERT: &[u8] = include_bytes!("tls/cert.der");
+const KEY: &[u8] = include_bytes!("tls/key.der");
+
+fn configs() -> (Arc<ServerConfig>, Arc<ClientConfig>) {
+    let server = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![CertificateDer::from(CERT)],
+            PrivatePkcs8KeyDer::from(KEY).into(),
+        )
+        .unwrap();
+
+    let mut roots = RootCertStore::empty();
+    roots.add(CertificateDer::from(CA)).unwrap();
+
+    let client = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    (Arc::new(server), Arc::new(client))
+}
+
+async fn pair() -> io::Result<(TlsStream<TcpStream>, TlsStream<TcpStream>)> {
+    let (server_config, client_config) = configs();
+
+    let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+    let addr = listener.get_ref().local_addr()?;
+
+    let client = Async::<TcpStream>::connect(addr).await?;
+    let server = listener.accept().await?.0;
+
+    let client = TlsStream::new(
+        client,
+        ClientConnection::new(client_config, "localhost".try_into().unwrap())
+            .map_err(io::Error::other)?,
+    );
+    let server = TlsStream::new(
+        server,
+        ServerConnection::new(server_config).map_err(io::Error::other)?,
+    );
+
+    Ok((client, server))
+}
+
+#[test]
+fn echo() -> io::Result<()> {
+    future::block_on(async {
+        let (mut client, mut server) = pair().await?;
+
+        let server = async move {
+            let mut buf = [0; 4];
+            let mut len = 0;
+
+            while len < buf.len() {
+                len += server.read(&mut buf[len..]).await?;
+            }
+
+            assert_eq!(&buf, b"ping");
+
+            server.write_all(b"pong").await?;
+            server.close().await?;
+
+            // The client closes its session as well
+            assert_eq!(server.read(&mut buf).await?, 0);
+
+            io::Result::Ok(())
+        };
+
+        let client = async move {
+            client.write_all(b"ping").await?;
+
+            let mut buf = Vec::new();
+            AsyncReadExt::read_to_end(&mut client, &mut buf).await?;
+
+            assert_eq!(buf, b"pong");
+
+            AsyncWriteExt::close(&mut client).await?;
+
+            io::Result::Ok(())
+        };
+
+        let (server, client) = future::zip(server, client).await;
+
+        server?;
+        client
+    })
+}
+
+#[test]
+fn untrusted_certificate() -> io::Result<()> {
+    future::block_on(async {
+        let (server_config, _) = configs();
+
+        // Trust nothing on the client side
+        let client_config = ClientConfig::builder()
+            .with_root_certificates(RootCertStore::empty())
+            .with_no_client_auth();
+
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0))?;
+        let addr = listener.get_ref().local_addr()?;
+
+        let mut client = TlsStream::new(
+            Async::<TcpStream>::connect(addr).await?,
+            ClientConnection::new(Arc::new(client_config), "localhost".try_into().unwrap())
+                .map_err(io::Error::other)?,
+        );
+        let mut server = TlsStream::new(
+            listener.accept().await?.0,
+            ServerConnection::new(server_config).map_err(io::Error::other)?,
+        );
+
+        let (client, server) = future::zip(client.handshake(), server.handshake()).await;
+
+        assert_eq!(client.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        // The client has sent an alert
+        assert_eq!(server.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        Ok(())
+    })
+}